@@ -0,0 +1,82 @@
+/* C interface to frat-xor: push FRAT-XOR proof steps in-process and elaborate them to XLRUP.
+ *
+ * Steps are pushed in proof order, as they would be written to a FRAT-XOR file, and kept in
+ * memory until frat_xor_elaborate; past 64 MiB (see frat_xor_spill_after) they are moved to
+ * a temporary file, which is removed again by frat_xor_elaborate or frat_xor_free. Literal and hint arrays are passed as a pointer and a length,
+ * without the terminating 0. An optional hint or unit list is omitted by passing a null pointer.
+ *
+ * Every function returning int returns 0 on success, and otherwise the exit code that
+ * `frat-xor elab` would use (1: invalid step, 3: malformed proof, 4: I/O error,
+ * 101: internal error); frat_xor_last_error then describes the problem.
+ */
+
+#ifndef FRAT_XOR_H
+#define FRAT_XOR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct FratXor frat_xor;
+
+#define FRAT_XOR_FULL 1       /* elaborate every step (--full) */
+#define FRAT_XOR_VALIDATE 2   /* reject invalid hints and unfinalized XORs and BNNs (-s) */
+#define FRAT_XOR_ALL_HINTS 4  /* reject invalid or missing hints (-ss) */
+#define FRAT_XOR_VERIFY 8     /* check the XLRUP proof with the built-in checker (-v) */
+#define FRAT_XOR_COMMENTS 16  /* copy comments into the XLRUP proof (-c) */
+#define FRAT_XOR_BINARY 32    /* write binary XLRUP (-b) */
+
+frat_xor *frat_xor_new(void);
+void frat_xor_free(frat_xor *h);
+const char *frat_xor_last_error(const frat_xor *h);
+/* keep at most bytes bytes of steps in memory before moving them to a temporary file */
+void frat_xor_spill_after(frat_xor *h, size_t bytes);
+
+int frat_xor_comment(frat_xor *h, const char *s);
+int frat_xor_orig(frat_xor *h, uint64_t id, const int64_t *lits, size_t n);
+int frat_xor_add(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  const int64_t *hints, size_t nh);
+int frat_xor_del(frat_xor *h, uint64_t id, const int64_t *lits, size_t n);
+int frat_xor_final(frat_xor *h, uint64_t id, const int64_t *lits, size_t n);
+/* pairs holds npairs pairs (from, to) */
+int frat_xor_reloc(frat_xor *h, const uint64_t *pairs, size_t npairs);
+int frat_xor_todo(frat_xor *h, uint64_t id);
+
+int frat_xor_orig_xor(frat_xor *h, uint64_t id, const int64_t *lits, size_t n);
+/* hints are XOR IDs, units are clause IDs */
+int frat_xor_add_xor(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  const int64_t *hints, size_t nh, const uint64_t *units, size_t nu);
+int frat_xor_del_xor(frat_xor *h, uint64_t id, const int64_t *lits, size_t n);
+int frat_xor_final_xor(frat_xor *h, uint64_t id, const int64_t *lits, size_t n);
+/* clause implied by the XORs in hints */
+int frat_xor_imply(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  const int64_t *hints, size_t nh);
+/* XOR implied by the clauses in hints */
+int frat_xor_imply_xor(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  const int64_t *hints, size_t nh);
+
+/* BNN lits >= cutoff <-> out, where out = 0 means no output literal */
+int frat_xor_orig_bnn(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  int64_t cutoff, int64_t out);
+int frat_xor_add_bnn(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  int64_t cutoff, int64_t out, const int64_t *hints, size_t nh);
+int frat_xor_del_bnn(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  int64_t cutoff, int64_t out);
+int frat_xor_final_bnn(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  int64_t cutoff, int64_t out);
+/* clause implied by the BNN hints[0], with unit clauses */
+int frat_xor_bnn_imply(frat_xor *h, uint64_t id, const int64_t *lits, size_t n,
+  const int64_t *hints, size_t nh, const uint64_t *units, size_t nu);
+
+/* Elaborate the pushed steps against the formula at xnf, writing XLRUP to xlrup
+ * (or nowhere if it is null). The steps are consumed, so this can only be called once. */
+int frat_xor_elaborate(frat_xor *h, const char *xnf, const char *xlrup, int flags);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,62 @@
+/* Pushes the proof of example/test_1 through the C interface and elaborates it, both from
+ * memory and from a temporary file, then checks that misuse is reported.
+ * Run with `make -C capi test`. */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include "frat_xor.h"
+
+#define CHECK(call) do { \
+  if ((call) != 0) { \
+    fprintf(stderr, "%s:%d: %s failed: %s\n", __FILE__, __LINE__, #call, frat_xor_last_error(h)); \
+    exit(1); \
+  } \
+} while (0)
+
+#define EXPECT_FAIL(call) do { \
+  if ((call) == 0) { fprintf(stderr, "%s:%d: %s should fail\n", __FILE__, __LINE__, #call); exit(1); } \
+  printf("expected error: %s\n", frat_xor_last_error(h)); \
+} while (0)
+
+static int64_t pos[] = {1}, neg[] = {-1}, h1[] = {1}, h12[] = {1, 2};
+
+static void push_test_1(frat_xor *h) {
+  CHECK(frat_xor_orig(h, 1, pos, 1));
+  CHECK(frat_xor_orig_xor(h, 1, neg, 1));
+  CHECK(frat_xor_imply(h, 2, neg, 1, h1, 1));
+  CHECK(frat_xor_add(h, 3, NULL, 0, h12, 2));
+  CHECK(frat_xor_final(h, 1, pos, 1));
+  CHECK(frat_xor_final(h, 2, neg, 1));
+  CHECK(frat_xor_final(h, 3, NULL, 0));
+  CHECK(frat_xor_final_xor(h, 1, neg, 1));
+}
+
+int main(int argc, char **argv) {
+  const char *xnf = argc > 1 ? argv[1] : "../example/test_1.xnf";
+  const char *out = argc > 2 ? argv[2] : "test_1.xlrup";
+
+  frat_xor *h = frat_xor_new();
+  push_test_1(h);
+  CHECK(frat_xor_elaborate(h, xnf, out, FRAT_XOR_VERIFY));
+  printf("elaborated to %s\n", out);
+  EXPECT_FAIL(frat_xor_elaborate(h, xnf, out, 0));
+  frat_xor_free(h);
+
+  /* the same proof, moved to a temporary file from the first step on */
+  h = frat_xor_new();
+  frat_xor_spill_after(h, 0);
+  push_test_1(h);
+  CHECK(frat_xor_elaborate(h, xnf, out, FRAT_XOR_VERIFY));
+  printf("elaborated to %s from a temporary file\n", out);
+  frat_xor_free(h);
+
+  /* a proof without the empty clause */
+  h = frat_xor_new();
+  CHECK(frat_xor_orig(h, 1, pos, 1));
+  CHECK(frat_xor_final(h, 1, pos, 1));
+  EXPECT_FAIL(frat_xor_elaborate(h, xnf, NULL, 0));
+  frat_xor_free(h);
+
+  printf("ok\n");
+  return 0;
+}
@@ -1,8 +1,15 @@
 use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom};
 use super::parser::*;
+use super::error::{FratError, Result};
 pub use super::parser::{Proof, Step, ElabStep};
 
+/// A source of segments read backwards, which knows where the last one started.
+pub trait Segments: Iterator<Item=Segment> {
+  /// The byte offset of the last segment returned.
+  fn offset(&self) -> usize;
+}
+
 pub struct VecBackParser(pub Vec<u8>);
 
 impl Iterator for VecBackParser {
@@ -16,6 +23,10 @@ impl Iterator for VecBackParser {
   }
 }
 
+impl Segments for VecBackParser {
+  fn offset(&self) -> usize { self.0.len() }
+}
+
 pub struct BackParser<M: Mode> {
   file: File,
   remaining: usize,
@@ -25,6 +36,8 @@ pub struct BackParser<M: Mode> {
   free: Vec<Box<[u8; BUFFER_SIZE]>>,
   mode: M,
   scan: M::BackScanState,
+  /// The byte offset of the last segment returned.
+  offset: usize,
 }
 
 impl<M: Mode> BackParser<M> {
@@ -43,6 +56,7 @@ impl<M: Mode> BackParser<M> {
       free: Vec::new(),
       scan: mode.new_back_scan(),
       mode,
+      offset: len,
     })
   }
 
@@ -56,8 +70,11 @@ impl<M: Mode> BackParser<M> {
     Ok(Some(buf))
   }
 
+
   fn parse_segment_from(&mut self, b: usize, i: usize) -> Segment {
-    let seg_start = || (self.remaining + (self.buffers.len() - (b + 1))) * BUFFER_SIZE + i;
+    self.offset = (self.remaining + (self.buffers.len() - (b + 1))) * BUFFER_SIZE + i;
+    let offset = self.offset;
+    let seg_start = || offset;
     if b == 0 {
       let res = self.mode.segment(seg_start, self.buffers[0][i..self.pos].iter().copied());
       self.pos = i;
@@ -74,18 +91,23 @@ impl<M: Mode> BackParser<M> {
   }
 }
 
+impl<M: Mode> Segments for BackParser<M> {
+  fn offset(&self) -> usize { self.offset }
+}
+
 impl<M: Mode> Iterator for BackParser<M> {
   type Item = Segment;
 
   fn next(&mut self) -> Option<Segment> {
     for b in 0.. {
       let buf: &[u8; BUFFER_SIZE] = match self.buffers.get(b) {
-        None => match self.read_chunk().expect("could not read from proof file") {
-          None => {
+        None => match self.read_chunk() {
+          Err(e) => return Some(Segment::Error(e.into())),
+          Ok(None) => {
             if b == 1 && self.pos == 0 { break }
             return Some(self.parse_segment_from(b-1, 0))
           },
-          Some(buf) => { self.buffers.push(buf); self.buffers.last().unwrap() }
+          Ok(Some(buf)) => { self.buffers.push(buf); self.buffers.last().unwrap() }
         },
         Some(buf) => buf
       };
@@ -103,235 +125,213 @@ impl<M: Mode> Iterator for BackParser<M> {
   }
 }
 
-pub struct StepIter<I>(pub I);
-
-impl<I: Iterator<Item=Segment>> Iterator for StepIter<I> {
-  type Item = Step;
-
-  fn next(&mut self) -> Option<Step> {
+/// Make an error for a segment that does not fit in a step, using the
+/// first step found among the following segments to say where it is.
+fn malformed_near(it: &mut impl Iterator<Item=Segment>, msg: &str, mut next: Option<Segment>) -> FratError {
+  if next.is_none() {
+    next = it.next()
+  }
 
-    fn _panic<I: Iterator<Item=Segment>>(self_ref: &mut StepIter<I>, msg: &str, mut next: Option<Segment>) -> Option<Step> {
-      if let None = next {
-        next = self_ref.0.next()
+  loop {
+    let (kind, idx) = match next {
+      Some(Segment::Orig(idx, _)) => ("orig", idx),
+      Some(Segment::Add(idx, _)) => ("add", idx),
+      Some(Segment::Del(idx, _)) => ("del", idx),
+      Some(Segment::Final(idx, _)) => ("final", idx),
+      Some(Segment::Xor(idx, _)) => match it.next() {
+        Some(Segment::DelHead()) => ("del-xor", idx),
+        Some(Segment::FinalHead()) => ("final-xor", idx),
+        _ => ("xor", idx),
       }
-
-      loop {
-        match next {
-          Some(Segment::Orig(idx, _)) => panic!("orig step {}: {}", idx, msg),
-          Some(Segment::Add(idx, _)) => panic!("add step {}: {}", idx, msg),
-          Some(Segment::Del(idx, _)) => panic!("del step {}: {}", idx, msg),
-          Some(Segment::Final(idx, _)) => panic!("final step {}: {}", idx, msg),
-          Some(Segment::Xor(idx, _)) => match self_ref.0.next() {
-            Some(Segment::DelHead()) => panic!("del-xor step {}: {}", idx, msg),
-            Some(Segment::FinalHead()) => panic!("final-xor step {}: {}", idx, msg),
-            _ => panic!("xor step {}: {}", idx, msg),
-          }
-          Some(Segment::BnnLhs(idx, _)) => match self_ref.0.next() {
-            Some(Segment::DelHead()) => panic!("del-bnn step {}: {}", idx, msg),
-            Some(Segment::FinalHead()) => panic!("final-bnn step {}: {}", idx, msg),
-            _ => panic!("bnn step {}: {}", idx, msg),
-          }
-          Some(Segment::Imply(idx, _)) => panic!("imply step {}: {}", idx, msg),
-          None => panic!("{}", msg),
-          _ => { next = self_ref.0.next() },
-        }
+      Some(Segment::BnnLhs(idx, _)) => match it.next() {
+        Some(Segment::DelHead()) => ("del-bnn", idx),
+        Some(Segment::FinalHead()) => ("final-bnn", idx),
+        _ => ("bnn", idx),
       }
-    }
+      Some(Segment::Imply(idx, _)) => ("imply", idx),
+      None => return malformed!("{}", msg),
+      _ => { next = it.next(); continue },
+    };
+    return malformed!("{}", msg).at(kind, Some(idx))
+  }
+}
+
+pub struct StepIter<I>(pub I);
+
+impl<I: Iterator<Item=Segment>> Iterator for StepIter<I> {
+  type Item = Result<Step>;
 
-    match self.0.next() {
-      None => None,
-      Some(Segment::Comment(s)) => Some(Step::Comment(s)),
-      Some(Segment::Orig(idx, vec)) => Some(Step::Orig(idx, vec)),
-      Some(Segment::Add(idx, vec)) => Some(Step::Add(idx, AddStep(vec), None)),
-      Some(Segment::Del(idx, vec)) => Some(Step::Del(idx, vec)),
-      Some(Segment::Reloc(relocs)) => Some(Step::Reloc(relocs)),
-      Some(Segment::Final(idx, vec)) => Some(Step::Final(idx, vec)),
+  fn next(&mut self) -> Option<Result<Step>> {
+    Some(Ok(match self.0.next() {
+      None => return None,
+      Some(Segment::Comment(s)) => Step::Comment(s),
+      Some(Segment::Orig(idx, vec)) => Step::Orig(idx, vec),
+      Some(Segment::Add(idx, vec)) => Step::Add(idx, AddStep(vec), None),
+      Some(Segment::Del(idx, vec)) => Step::Del(idx, vec),
+      Some(Segment::Reloc(relocs)) => Step::Reloc(relocs),
+      Some(Segment::Final(idx, vec)) => Step::Final(idx, vec),
       Some(Segment::LProof(steps)) => match self.0.next() {
         Some(Segment::Add(idx, vec)) =>
-          Some(Step::Add(idx, AddStep(vec), Some(Proof::LRAT(steps)))),
+          Step::Add(idx, AddStep(vec), Some(Proof::LRAT(steps))),
         Some(Segment::Xor(idx, vec)) => match self.0.next() {
           Some(Segment::AddHead()) => 
-            Some(Step::AddXor(idx, vec, Some(Proof::LRAT(steps)), None)),
+            Step::AddXor(idx, vec, Some(Proof::LRAT(steps)), None),
           Some(Segment::ImplyHead()) =>
-            Some(Step::ImplyXor(idx, vec, Some(Proof::LRAT(steps)))),
-          Some(Segment::DelHead()) => panic!("del-xor step {}: 'x' 'l' step not preceded by 'a' or 'i' step", idx),
-          Some(Segment::FinalHead()) => panic!("final-xor step {}: 'x' 'l' step not preceded by 'a' or 'i' step", idx),
-          _ => panic!("xor step {}: 'x' 'l' step not preceded by 'a' or 'i' step", idx)
+            Step::ImplyXor(idx, vec, Some(Proof::LRAT(steps))),
+          Some(Segment::DelHead()) => return Some(Err(malformed!("'x' 'l' step not preceded by 'a' or 'i' step").at("del-xor", Some(idx)))),
+          Some(Segment::FinalHead()) => return Some(Err(malformed!("'x' 'l' step not preceded by 'a' or 'i' step").at("final-xor", Some(idx)))),
+          _ => return Some(Err(malformed!("'x' 'l' step not preceded by 'a' or 'i' step").at("xor", Some(idx))))
         }
         Some(Segment::Imply(idx, vec)) =>
-          Some(Step::Imply(idx, vec, Some(Proof::LRAT(steps)))),
+          Step::Imply(idx, vec, Some(Proof::LRAT(steps))),
         Some(Segment::BnnImply()) => match self.0.next() {
-          Some(Segment::Imply(idx, vec)) => Some(Step::BnnImply(idx, vec, Some(Proof::LRAT(steps)), None)),
-          other => _panic(self, "'b' 'l' step not preceded by 'i' step", other)
+          Some(Segment::Imply(idx, vec)) => Step::BnnImply(idx, vec, Some(Proof::LRAT(steps)), None),
+          other => return Some(Err(malformed_near(&mut self.0, "'b' 'l' step not preceded by 'i' step", other)))
         }
         Some(Segment::BnnRhs(rhs, out)) => match self.0.next() {
           Some(Segment::BnnLhs(idx, vec)) => match self.0.next() {
-            Some(Segment::AddHead()) => Some(Step::AddBnn(idx, vec, rhs, out, Some(Proof::LRAT(steps)))),
-            _ => panic!("add-bnn step {}: 'b' 'k' 'l' step not preceded by 'a' step", idx)
+            Some(Segment::AddHead()) => Step::AddBnn(idx, vec, rhs, out, Some(Proof::LRAT(steps))),
+            _ => return Some(Err(malformed!("'b' 'k' 'l' step not preceded by 'a' step").at("add-bnn", Some(idx))))
           }
-          other => _panic(self, "'k' 'l' step not preceded by 'a' 'b' step", other)
+          other => return Some(Err(malformed_near(&mut self.0, "'k' 'l' step not preceded by 'a' 'b' step", other)))
         }                                                     
-        other => _panic(self, "'l' step not preceded by 'a', 'x', 'i', 'b', or 'k' step", other)
+        other => return Some(Err(malformed_near(&mut self.0, "'l' step not preceded by 'a', 'x', 'i', 'b', or 'k' step", other)))
       },
-      Some(Segment::Todo(idx)) => Some(Step::Todo(idx)),
+      Some(Segment::Todo(idx)) => Step::Todo(idx),
       Some(Segment::Xor(idx, vec)) => match self.0.next() {
-        Some(Segment::OrigHead()) => Some(Step::OrigXor(idx, vec)),
-        Some(Segment::AddHead()) => Some(Step::AddXor(idx, vec, None, None)),
-        Some(Segment::DelHead()) => Some(Step::DelXor(idx, vec)),
-        Some(Segment::ImplyHead()) => Some(Step::ImplyXor(idx, vec, None)),
-        Some(Segment::FinalHead()) => Some(Step::FinalXor(idx, vec)),
-        _ => panic!("xor step {}: 'x' step not preceded by 'o', 'a', 'd', 'i', or 'f' step", idx)
+        Some(Segment::OrigHead()) => Step::OrigXor(idx, vec),
+        Some(Segment::AddHead()) => Step::AddXor(idx, vec, None, None),
+        Some(Segment::DelHead()) => Step::DelXor(idx, vec),
+        Some(Segment::ImplyHead()) => Step::ImplyXor(idx, vec, None),
+        Some(Segment::FinalHead()) => Step::FinalXor(idx, vec),
+        _ => return Some(Err(malformed!("'x' step not preceded by 'o', 'a', 'd', 'i', or 'f' step").at("xor", Some(idx))))
       }
-      Some(Segment::OrigHead()) => _panic(self, "'o' step not followed by a clause, 'x' step, or 'b' step", None),
-      Some(Segment::AddHead()) => _panic(self, "'a' step not followed by a clause, 'x', or 'b' step", None),
-      Some(Segment::DelHead()) => _panic(self, "'d' step not followed by a clause, 'x' step, or 'b' step", None),
-      Some(Segment::Imply(idx, vec)) => Some(Step::Imply(idx, vec, None)),
-      Some(Segment::ImplyHead()) => _panic(self, "'i' step not followed by a clause or 'x' step", None),
-      Some(Segment::FinalHead()) => _panic(self, "'f' step not followed by a clause, 'x' step, or 'b' step", None),
+      Some(Segment::OrigHead()) => return Some(Err(malformed_near(&mut self.0, "'o' step not followed by a clause, 'x' step, or 'b' step", None))),
+      Some(Segment::AddHead()) => return Some(Err(malformed_near(&mut self.0, "'a' step not followed by a clause, 'x', or 'b' step", None))),
+      Some(Segment::DelHead()) => return Some(Err(malformed_near(&mut self.0, "'d' step not followed by a clause, 'x' step, or 'b' step", None))),
+      Some(Segment::Imply(idx, vec)) => Step::Imply(idx, vec, None),
+      Some(Segment::ImplyHead()) => return Some(Err(malformed_near(&mut self.0, "'i' step not followed by a clause or 'x' step", None))),
+      Some(Segment::FinalHead()) => return Some(Err(malformed_near(&mut self.0, "'f' step not followed by a clause, 'x' step, or 'b' step", None))),
       Some(Segment::Unit(units)) => match self.0.next() {
         Some(Segment::LProof(steps)) => match self.0.next() {
           Some(Segment::Xor(idx, vec)) => match self.0.next() {
-            Some(Segment::AddHead()) => Some(Step::AddXor(idx, vec, Some(Proof::LRAT(steps)), Some(Proof::Unit(units)))),
-            Some(Segment::DelHead()) => panic!("del-xor step {}: 'x' 'l' 'u' step not preceded by 'a' step", idx),
-            Some(Segment::FinalHead()) => panic!("final-xor step {}: 'x' 'l' 'u' step not preceded by 'a' step", idx),
-            _ => panic!("xor step {}: 'x' 'l' 'u' step not preceded by 'a' step", idx),
+            Some(Segment::AddHead()) => Step::AddXor(idx, vec, Some(Proof::LRAT(steps)), Some(Proof::Unit(units))),
+            Some(Segment::DelHead()) => return Some(Err(malformed!("'x' 'l' 'u' step not preceded by 'a' step").at("del-xor", Some(idx)))),
+            Some(Segment::FinalHead()) => return Some(Err(malformed!("'x' 'l' 'u' step not preceded by 'a' step").at("final-xor", Some(idx)))),
+            _ => return Some(Err(malformed!("'x' 'l' 'u' step not preceded by 'a' step").at("xor", Some(idx)))),
           }
           Some(Segment::BnnImply()) => match self.0.next() {
-            Some(Segment::Imply(idx, vec)) => Some(Step::BnnImply(idx, vec, Some(Proof::LRAT(steps)), Some(Proof::Unit(units)))),
-            other => _panic(self, "'b' 'l' 'u' step not preceded by 'i' step", other)
+            Some(Segment::Imply(idx, vec)) => Step::BnnImply(idx, vec, Some(Proof::LRAT(steps)), Some(Proof::Unit(units))),
+            other => return Some(Err(malformed_near(&mut self.0, "'b' 'l' 'u' step not preceded by 'i' step", other)))
           }
-          other => _panic(self, "'l' 'u' step not preceded by 'x' or 'i' 'b' step", other),
+          other => return Some(Err(malformed_near(&mut self.0, "'l' 'u' step not preceded by 'x' or 'i' 'b' step", other))),
         }
-        other => _panic(self, "'u' step not preceded by 'l' step", other),
+        // An XOR addition whose hints are left for `elab` to find
+        Some(Segment::Xor(idx, vec)) => match self.0.next() {
+          Some(Segment::AddHead()) => Step::AddXor(idx, vec, None, Some(Proof::Unit(units))),
+          _ => return Some(Err(malformed!("'x' 'u' step not preceded by 'a' step").at("xor", Some(idx)))),
+        }
+        other => return Some(Err(malformed_near(&mut self.0, "'u' step not preceded by 'l' step", other))),
       }
       Some(Segment::BnnRhs(rhs, out)) => match self.0.next() {
         Some(Segment::BnnLhs(idx, vec)) => match self.0.next() {
-          Some(Segment::OrigHead()) => Some(Step::OrigBnn(idx, vec, rhs, out)),
-          Some(Segment::DelHead()) => Some(Step::DelBnn(idx, vec, rhs, out)),
-          Some(Segment::FinalHead()) => Some(Step::FinalBnn(idx, vec, rhs, out)),
-          _ => panic!("bnn step {}: 'b' step not preceded by 'o', 'd', or 'f' step", idx)
+          Some(Segment::OrigHead()) => Step::OrigBnn(idx, vec, rhs, out),
+          Some(Segment::DelHead()) => Step::DelBnn(idx, vec, rhs, out),
+          Some(Segment::FinalHead()) => Step::FinalBnn(idx, vec, rhs, out),
+          _ => return Some(Err(malformed!("'b' step not preceded by 'o', 'd', or 'f' step").at("bnn", Some(idx))))
         }
-        other => _panic(self, "'k' step not preceded by 'b' step", other)
+        other => return Some(Err(malformed_near(&mut self.0, "'k' step not preceded by 'b' step", other)))
       }
-      Some(Segment::BnnLhs(idx, _vec)) => panic!("bnn step {}: 'b' step not followed by 'k' step", idx),
-      Some(Segment::BnnImply()) => _panic(self, "wrong format for 'b' step", None), 
-      Some(Segment::Error()) => _panic(self, "parse error", None),
-    }
+      Some(Segment::BnnLhs(idx, _vec)) => return Some(Err(malformed!("'b' step not followed by 'k' step").at("bnn", Some(idx)))),
+      Some(Segment::BnnImply()) => return Some(Err(malformed_near(&mut self.0, "wrong format for 'b' step", None))), 
+      Some(Segment::Error(e)) => return Some(Err(e)),
+    }))
   }
 }
 
 pub struct ElabStepIter<I>(pub I);
 
 impl<I: Iterator<Item=Segment>> Iterator for ElabStepIter<I> {
-  type Item = ElabStep;
-
-  fn next(&mut self) -> Option<ElabStep> {
+  type Item = Result<ElabStep>;
 
-    fn _panic<I: Iterator<Item=Segment>>(self_ref: &mut ElabStepIter<I>, msg: &str, mut next: Option<Segment>) -> Option<ElabStep> {
-      if let None = next {
-        next = self_ref.0.next()
-      }
-
-      loop {
-        match next {
-          Some(Segment::Orig(idx, _)) => panic!("orig step {}: {}", idx, msg),
-          Some(Segment::Add(idx, _)) => panic!("add step {}: {}", idx, msg),
-          Some(Segment::Del(idx, _)) => panic!("del step {}: {}", idx, msg),
-          Some(Segment::Final(idx, _)) => panic!("final step {}: {}", idx, msg),
-          Some(Segment::Xor(idx, _)) => match self_ref.0.next() {
-            Some(Segment::DelHead()) => panic!("del-xor step {}: {}", idx, msg),
-            Some(Segment::FinalHead()) => panic!("final-xor step {}: {}", idx, msg),
-            _ => panic!("xor step {}: {}", idx, msg),
-          }
-          Some(Segment::BnnLhs(idx, _)) => match self_ref.0.next() {
-            Some(Segment::DelHead()) => panic!("del-bnn step {}: {}", idx, msg),
-            Some(Segment::FinalHead()) => panic!("final-bnn step {}: {}", idx, msg),
-            _ => panic!("bnn step {}: {}", idx, msg),
-          }
-          Some(Segment::Imply(idx, _)) => panic!("imply step {}: {}", idx, msg),
-          None => panic!("{}", msg),
-          _ => { next = self_ref.0.next() },
-        }
-      }
-    }
-
-    match self.0.next() {
-      None => None,
-      Some(Segment::Comment(s)) => Some(ElabStep::Comment(s)),
-      Some(Segment::Orig(idx, vec)) => Some(ElabStep::Orig(idx, vec)),
-      Some(Segment::Add(idx, _)) => panic!("add step {}: add step has no proof", idx),
+  fn next(&mut self) -> Option<Result<ElabStep>> {
+    Some(Ok(match self.0.next() {
+      None => return None,
+      Some(Segment::Comment(s)) => ElabStep::Comment(s),
+      Some(Segment::Orig(idx, vec)) => ElabStep::Orig(idx, vec),
+      Some(Segment::Add(idx, _)) => return Some(Err(malformed!("add step has no proof").at("add", Some(idx)))),
       Some(Segment::Del(idx, vec)) =>
-        {assert!(vec.is_empty()); Some(ElabStep::Del(idx))},
-      Some(Segment::Reloc(relocs)) => Some(ElabStep::Reloc(relocs)),
+        {assert!(vec.is_empty()); ElabStep::Del(idx)},
+      Some(Segment::Reloc(relocs)) => ElabStep::Reloc(relocs),
       Some(Segment::LProof(steps)) => match self.0.next() {
         Some(Segment::Add(idx, vec)) =>
-          Some(ElabStep::Add(idx, AddStep(vec), steps)),
+          ElabStep::Add(idx, AddStep(vec), steps),
         Some(Segment::Xor(idx, vec)) => match self.0.next() {
-          Some(Segment::AddHead()) => Some(ElabStep::AddXor(idx, vec, steps, None)),
-          Some(Segment::ImplyHead()) => Some(ElabStep::ImplyXor(idx, vec, steps)),
-          _ => panic!("xor step {}: 'x' 'l' step not preceded by 'a' or 'i' step", idx)
+          Some(Segment::AddHead()) => ElabStep::AddXor(idx, vec, steps, None),
+          Some(Segment::ImplyHead()) => ElabStep::ImplyXor(idx, vec, steps),
+          _ => return Some(Err(malformed!("'x' 'l' step not preceded by 'a' or 'i' step").at("xor", Some(idx))))
         }
         Some(Segment::Imply(idx, vec)) =>
-          Some(ElabStep::Imply(idx, vec, steps)),
+          ElabStep::Imply(idx, vec, steps),
         Some(Segment::BnnImply()) => match self.0.next() {
-          Some(Segment::Imply(idx, vec)) => Some(ElabStep::BnnImply(idx, vec, steps, None)),
-          _ => panic!("'b' 'l' step not preceded by 'i' step")
+          Some(Segment::Imply(idx, vec)) => ElabStep::BnnImply(idx, vec, steps, None),
+          _ => return Some(Err(malformed!("'b' 'l' step not preceded by 'i' step")))
         }
         Some(Segment::BnnRhs(rhs, out)) => match self.0.next() {
           Some(Segment::BnnLhs(idx, vec)) => match self.0.next() {
-            Some(Segment::AddHead()) => Some(ElabStep::AddBnn(idx, vec, rhs, out, steps)),
-            _ => panic!("'b' 'k' 'l' step not preceded by 'a' step")
+            Some(Segment::AddHead()) => ElabStep::AddBnn(idx, vec, rhs, out, steps),
+            _ => return Some(Err(malformed!("'b' 'k' 'l' step not preceded by 'a' step")))
           }
-          _ => panic!("'k' 'l' step not preceded by 'a' 'b' step")
+          _ => return Some(Err(malformed!("'k' 'l' step not preceded by 'a' 'b' step")))
         }
-        _ => panic!("'l' step not preceded by 'a', 'x', 'i', 'i' 'b', or 'a' 'b' 'k' step")
+        _ => return Some(Err(malformed!("'l' step not preceded by 'a', 'x', 'i', 'i' 'b', or 'a' 'b' 'k' step")))
       },
-      Some(Segment::Final(idx, _)) => panic!("final step {}: unexpected 'f' segment", idx),
-      Some(Segment::Todo(_)) => self.next(),
+      Some(Segment::Final(idx, _)) => return Some(Err(malformed!("unexpected 'f' segment").at("final", Some(idx)))),
+      Some(Segment::Todo(_)) => return self.next(),
       Some(Segment::Xor(idx, vec)) => match self.0.next() {
-        Some(Segment::OrigHead()) => Some(ElabStep::OrigXor(idx, vec)),
-        Some(Segment::AddHead()) => panic!("add-xor step {}: add XOR step has no proof", idx),
+        Some(Segment::OrigHead()) => ElabStep::OrigXor(idx, vec),
+        Some(Segment::AddHead()) => return Some(Err(malformed!("add XOR step has no proof").at("add-xor", Some(idx)))),
         Some(Segment::DelHead()) =>
-          {assert!(vec.is_empty()); Some(ElabStep::DelXor(idx))},
-        Some(Segment::ImplyHead()) => panic!("imply-xor step {}: imply XOR step has no proof", idx),
-        Some(Segment::FinalHead()) => panic!("final-xor step {}: unexpected 'f x' segment", idx),
-        _ => panic!("xor step {}: 'x' step not preceded by 'o', 'a', 'd', 'i', or 'f' step", idx)
+          {assert!(vec.is_empty()); ElabStep::DelXor(idx)},
+        Some(Segment::ImplyHead()) => return Some(Err(malformed!("imply XOR step has no proof").at("imply-xor", Some(idx)))),
+        Some(Segment::FinalHead()) => return Some(Err(malformed!("unexpected 'f x' segment").at("final-xor", Some(idx)))),
+        _ => return Some(Err(malformed!("'x' step not preceded by 'o', 'a', 'd', 'i', or 'f' step").at("xor", Some(idx))))
       }
-      Some(Segment::OrigHead()) => panic!("'o' step not followed by a clause, 'x' step, or 'b' step"),
-      Some(Segment::AddHead()) => panic!("'a' step not followed by a clause, 'x', or 'b' step"),
-      Some(Segment::DelHead()) => panic!("'d' step not followed by a clause, 'x' step, or 'b' step"),
-      Some(Segment::Imply(idx, _)) => panic!("imply step {}: imply step has no proof", idx),
-      Some(Segment::ImplyHead()) => panic!("'i' step not followed by a clause or 'x' step"),
-      Some(Segment::FinalHead()) => panic!("unexpected 'f' segment"),
+      Some(Segment::OrigHead()) => return Some(Err(malformed!("'o' step not followed by a clause, 'x' step, or 'b' step"))),
+      Some(Segment::AddHead()) => return Some(Err(malformed!("'a' step not followed by a clause, 'x', or 'b' step"))),
+      Some(Segment::DelHead()) => return Some(Err(malformed!("'d' step not followed by a clause, 'x' step, or 'b' step"))),
+      Some(Segment::Imply(idx, _)) => return Some(Err(malformed!("imply step has no proof").at("imply", Some(idx)))),
+      Some(Segment::ImplyHead()) => return Some(Err(malformed!("'i' step not followed by a clause or 'x' step"))),
+      Some(Segment::FinalHead()) => return Some(Err(malformed!("unexpected 'f' segment"))),
       Some(Segment::Unit(units)) => match self.0.next() {
         Some(Segment::LProof(steps)) => match self.0.next() {
           Some(Segment::Xor(idx, vec)) => match self.0.next() {
-            Some(Segment::AddHead()) => Some(ElabStep::AddXor(idx, vec, steps, Some(Proof::Unit(units)))),
-            _ => panic!("xor step {}: 'x' 'l' 'u' step not preceded by 'a' step", idx),
+            Some(Segment::AddHead()) => ElabStep::AddXor(idx, vec, steps, Some(Proof::Unit(units))),
+            _ => return Some(Err(malformed!("'x' 'l' 'u' step not preceded by 'a' step").at("xor", Some(idx)))),
           }
           Some(Segment::BnnImply()) => match self.0.next() {
-            Some(Segment::Imply(idx, vec)) => Some(ElabStep::BnnImply(idx, vec, steps, Some(Proof::Unit(units)))),
-            _ => panic!("'b' 'l' 'u' step not preceded by 'i' step")
+            Some(Segment::Imply(idx, vec)) => ElabStep::BnnImply(idx, vec, steps, Some(Proof::Unit(units))),
+            _ => return Some(Err(malformed!("'b' 'l' 'u' step not preceded by 'i' step")))
           }
-          _ => panic!("'l' 'u' step not preceded by 'x' or 'i' 'b' step"),
+          _ => return Some(Err(malformed!("'l' 'u' step not preceded by 'x' or 'i' 'b' step"))),
         }
-        _ => panic!("'u' step not preceded by 'l' step"),
+        _ => return Some(Err(malformed!("'u' step not preceded by 'l' step"))),
       }
       Some(Segment::BnnRhs(rhs, out)) => match self.0.next() {
         Some(Segment::BnnLhs(idx, vec)) => match self.0.next() {
-          Some(Segment::OrigHead()) => Some(ElabStep::OrigBnn(idx, vec, rhs, out)),
-          Some(Segment::DelHead()) => panic!("del-bnn step {}: 'k' step should not exist for ElabStep", idx),
-          Some(Segment::FinalHead()) => panic!("final-bnn step {}: unexpected 'f b' segment", idx),
-          _ => panic!("bnn step {}: 'b' step not preceded by 'o', 'd', or 'f' step", idx)
+          Some(Segment::OrigHead()) => ElabStep::OrigBnn(idx, vec, rhs, out),
+          Some(Segment::DelHead()) => return Some(Err(malformed!("'k' step should not exist for ElabStep").at("del-bnn", Some(idx)))),
+          Some(Segment::FinalHead()) => return Some(Err(malformed!("unexpected 'f b' segment").at("final-bnn", Some(idx)))),
+          _ => return Some(Err(malformed!("'b' step not preceded by 'o', 'd', or 'f' step").at("bnn", Some(idx))))
         }
-        _ => panic!("'k' {} {} step not preceded by 'b' step", rhs, out)
+        _ => return Some(Err(malformed!("'k' {} {} step not preceded by 'b' step", rhs, out)))
       }
       Some(Segment::BnnLhs(idx, vec)) => match self.0.next() {
-        Some(Segment::DelHead()) => {assert!(vec.is_empty()); Some(ElabStep::DelBnn(idx))},
-        _ => panic!("bnn step {}: 'b' step not followed by 'k' step", idx),
+        Some(Segment::DelHead()) => {assert!(vec.is_empty()); ElabStep::DelBnn(idx)},
+        _ => return Some(Err(malformed!("'b' step not followed by 'k' step").at("bnn", Some(idx)))),
       }
-      Some(Segment::BnnImply()) => panic!("wrong format for 'b' step"), 
-      Some(Segment::Error()) => _panic(self, "parse error", None),
-    }
+      Some(Segment::BnnImply()) => return Some(Err(malformed!("wrong format for 'b' step"))), 
+      Some(Segment::Error(e)) => return Some(Err(e)),
+    }))
   }
 }
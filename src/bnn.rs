@@ -0,0 +1,56 @@
+/// A BNN constraint `l_1 + ... + l_n >= cutoff <-> out`. If `out` is 0 the constraint
+/// is just the cardinality constraint `l_1 + ... + l_n >= cutoff`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bnn {
+  pub lits: Vec<i64>,
+  pub cutoff: i64,
+  pub out: i64,
+}
+
+impl Bnn {
+  pub fn new(lits: &[i64], cutoff: i64, out: i64) -> Self {
+    Bnn { lits: lits.into(), cutoff, out }
+  }
+
+  /// Returns true if no extension of the partial assignment `is_true` satisfies the constraint.
+  pub fn violated(&self, is_true: impl Fn(i64) -> bool) -> bool {
+    let (mut tru, mut undef) = (0, 0);
+    for &l in &self.lits {
+      if is_true(l) { tru += 1 } else if !is_true(-l) { undef += 1 }
+    }
+    let (can_reach, can_miss) = (tru + undef >= self.cutoff, tru < self.cutoff);
+    match self.out {
+      0 => !can_reach,
+      o if is_true(o) => !can_reach,
+      o if is_true(-o) => !can_miss,
+      _ => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn violated(bnn: &Bnn, tru: &[i64]) -> bool { bnn.violated(|l| tru.contains(&l)) }
+
+  #[test] fn cardinality() {
+    let b = Bnn::new(&[1, 2, 3], 2, 0);
+    assert!(violated(&b, &[-1, -2]));
+    assert!(!violated(&b, &[-1]));
+    assert!(!violated(&b, &[1, 2, -3]));
+  }
+
+  #[test] fn reified() {
+    let b = Bnn::new(&[1, -2, 3], 2, 4);
+    // A true output needs the cutoff to be reachable
+    assert!(violated(&b, &[4, -1, 2]));
+    assert!(!violated(&b, &[4, -1]));
+    // A false output needs the cutoff to be missable
+    assert!(violated(&b, &[-4, 1, -2]));
+    assert!(!violated(&b, &[-4, 1]));
+    // An unassigned output can always be set to fit
+    assert!(!violated(&b, &[1, -2, 3]));
+    assert!(!violated(&b, &[-1, 2, -3]));
+  }
+}
@@ -0,0 +1,255 @@
+//! A C interface through which a solver pushes FRAT-XOR steps in-process and then
+//! asks for the XLRUP proof. The steps are kept in memory in binary FRAT-XOR format
+//! until they outgrow a size limit, after which they go to a temporary file.
+//! See `capi/frat_xor.h`.
+
+use std::ffi::{CStr, CString};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{process, ptr, slice};
+use crate::error::{FratError, Result};
+use crate::elab::{elaborate, elaborate_bytes, ElabOptions};
+use crate::writer::ProofWriter;
+
+pub const FRAT_XOR_FULL: c_int = 1;
+pub const FRAT_XOR_VALIDATE: c_int = 2;
+pub const FRAT_XOR_ALL_HINTS: c_int = 4;
+pub const FRAT_XOR_VERIFY: c_int = 8;
+pub const FRAT_XOR_COMMENTS: c_int = 16;
+pub const FRAT_XOR_BINARY: c_int = 32;
+
+/// The exit code used for panics inside the library.
+const PANICKED: c_int = 101;
+
+/// The default size in bytes past which the pushed steps are moved to a temporary file.
+const SPILL_AFTER: usize = 64 << 20;
+
+/// A temporary proof file, removed together with the `.temp` file that `elaborate` puts next to it.
+struct TempFile(PathBuf);
+
+impl TempFile {
+  fn new() -> Self {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNT.fetch_add(1, Ordering::Relaxed);
+    TempFile(std::env::temp_dir().join(format!("frat-xor-{}-{}.frat", process::id(), n)))
+  }
+}
+
+impl Drop for TempFile {
+  fn drop(&mut self) {
+    let mut elab_temp = self.0.as_os_str().to_owned();
+    elab_temp.push(".temp");
+    let _ = fs::remove_file(&self.0);
+    let _ = fs::remove_file(elab_temp);
+  }
+}
+
+/// The pushed steps: in memory up to the limit, and in a temporary file after that.
+enum Spill {
+  Mem(Vec<u8>, usize),
+  File(TempFile, BufWriter<File>),
+}
+
+impl Write for Spill {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    if let Spill::Mem(mem, limit) = self {
+      if mem.len() + buf.len() <= *limit {
+        mem.extend_from_slice(buf);
+        return Ok(buf.len())
+      }
+      let temp = TempFile::new();
+      let mut w = BufWriter::new(File::create(&temp.0)?);
+      w.write_all(mem)?;
+      *self = Spill::File(temp, w);
+    }
+    match self {
+      Spill::File(_, w) => w.write(buf),
+      Spill::Mem(..) => unreachable!(),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      Spill::File(_, w) => w.flush(),
+      Spill::Mem(..) => Ok(()),
+    }
+  }
+}
+
+pub struct FratXor {
+  /// The steps pushed so far, or `None` once the proof has been elaborated.
+  proof: Option<ProofWriter<Spill>>,
+  error: Option<CString>,
+}
+
+impl FratXor {
+  fn run(&mut self, f: impl FnOnce(&mut Option<ProofWriter<Spill>>) -> Result<()>) -> c_int {
+    let proof = &mut self.proof;
+    let (code, msg) = match catch_unwind(AssertUnwindSafe(|| f(proof))) {
+      Ok(Ok(())) => return 0,
+      Ok(Err(e)) => (e.exit_code(), e.to_string()),
+      Err(_) => (PANICKED, "internal error: frat-xor panicked".into()),
+    };
+    self.error = Some(CString::new(msg.replace('\0', " ")).unwrap());
+    code
+  }
+
+  fn step(&mut self, f: impl FnOnce(&mut ProofWriter<Spill>) -> Result<()>) -> c_int {
+    self.run(|p| match p {
+      Some(w) => f(w),
+      None => Err(malformed!("the proof has already been elaborated")),
+    })
+  }
+}
+
+/// Read `n` elements at `p`, where `p` may be null if `n` is 0.
+unsafe fn arr<'a, T>(p: *const T, n: usize) -> &'a [T] {
+  if n == 0 { &[] } else { slice::from_raw_parts(p, n) }
+}
+
+/// Like `arr`, but a null pointer means there is no list at all.
+unsafe fn opt_arr<'a, T>(p: *const T, n: usize) -> Option<&'a [T]> {
+  if p.is_null() { None } else { Some(arr(p, n)) }
+}
+
+unsafe fn path<'a>(p: *const c_char) -> Option<&'a Path> {
+  if p.is_null() { None } else { CStr::from_ptr(p).to_str().ok().map(Path::new) }
+}
+
+/// Create an empty proof. Free it with `frat_xor_free`.
+#[no_mangle]
+pub extern "C" fn frat_xor_new() -> *mut FratXor {
+  let proof = ProofWriter::new(Spill::Mem(Vec::new(), SPILL_AFTER), true);
+  Box::into_raw(Box::new(FratXor { proof: Some(proof), error: None }))
+}
+
+/// Keep at most `bytes` bytes of steps in memory before moving them to a temporary file.
+/// This has no effect once the steps have been moved.
+///
+/// # Safety
+/// `h` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn frat_xor_spill_after(h: *mut FratXor, bytes: usize) {
+  if let Some(proof) = &mut (*h).proof {
+    if let Spill::Mem(_, limit) = proof.get_mut() { *limit = bytes }
+  }
+}
+
+/// # Safety
+/// `h` must come from `frat_xor_new` and not have been freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn frat_xor_free(h: *mut FratXor) {
+  if !h.is_null() { drop(Box::from_raw(h)) }
+}
+
+/// The message of the last error, valid until the next failing call on `h`, or null.
+///
+/// # Safety
+/// `h` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn frat_xor_last_error(h: *const FratXor) -> *const c_char {
+  (*h).error.as_ref().map_or(ptr::null(), |e| e.as_ptr())
+}
+
+macro_rules! c_steps {
+  ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),*) => |$w:ident| $e:expr;)*) => {$(
+    $(#[$attr])*
+    ///
+    /// # Safety
+    /// `h` must be a live handle, and each pointer must point to as many elements as its length.
+    #[no_mangle]
+    pub unsafe extern "C" fn $name(h: *mut FratXor, $($arg: $ty),*) -> c_int {
+      (*h).step(|$w| $e)
+    }
+  )*}
+}
+
+c_steps! {
+  fn frat_xor_comment(s: *const c_char) =>
+    |w| w.comment(&CStr::from_ptr(s).to_string_lossy());
+  fn frat_xor_orig(id: u64, lits: *const i64, n: usize) =>
+    |w| w.orig(id, arr(lits, n));
+  /// Add a clause. `hints` may be null if there is no LRAT proof.
+  fn frat_xor_add(id: u64, lits: *const i64, n: usize, hints: *const i64, nh: usize) =>
+    |w| w.add(id, arr(lits, n), opt_arr(hints, nh));
+  fn frat_xor_del(id: u64, lits: *const i64, n: usize) =>
+    |w| w.del(id, arr(lits, n));
+  fn frat_xor_final(id: u64, lits: *const i64, n: usize) =>
+    |w| w.finalize(id, arr(lits, n));
+  /// Relocate clauses; `pairs` holds `npairs` pairs `from, to`.
+  fn frat_xor_reloc(pairs: *const u64, npairs: usize) =>
+    |w| {
+      let pairs: Vec<_> = arr(pairs, 2 * npairs).chunks(2).map(|p| (p[0], p[1])).collect();
+      w.reloc(&pairs)
+    };
+  fn frat_xor_todo(id: u64) =>
+    |w| w.todo(id);
+  fn frat_xor_orig_xor(id: u64, lits: *const i64, n: usize) =>
+    |w| w.orig_xor(id, arr(lits, n));
+  /// Add an XOR as the sum of the XORs `hints`, simplified by the unit clauses `units`.
+  fn frat_xor_add_xor(id: u64, lits: *const i64, n: usize,
+    hints: *const i64, nh: usize, units: *const u64, nu: usize) =>
+    |w| w.add_xor(id, arr(lits, n), opt_arr(hints, nh), opt_arr(units, nu));
+  fn frat_xor_del_xor(id: u64, lits: *const i64, n: usize) =>
+    |w| w.del_xor(id, arr(lits, n));
+  fn frat_xor_final_xor(id: u64, lits: *const i64, n: usize) =>
+    |w| w.finalize_xor(id, arr(lits, n));
+  /// Add a clause implied by the XORs `hints`.
+  fn frat_xor_imply(id: u64, lits: *const i64, n: usize, hints: *const i64, nh: usize) =>
+    |w| w.imply(id, arr(lits, n), opt_arr(hints, nh));
+  /// Add an XOR implied by the clauses `hints`.
+  fn frat_xor_imply_xor(id: u64, lits: *const i64, n: usize, hints: *const i64, nh: usize) =>
+    |w| w.imply_xor(id, arr(lits, n), opt_arr(hints, nh));
+  fn frat_xor_orig_bnn(id: u64, lits: *const i64, n: usize, cutoff: i64, out: i64) =>
+    |w| w.orig_bnn(id, arr(lits, n), cutoff, out);
+  fn frat_xor_add_bnn(id: u64, lits: *const i64, n: usize, cutoff: i64, out: i64,
+    hints: *const i64, nh: usize) =>
+    |w| w.add_bnn(id, arr(lits, n), cutoff, out, opt_arr(hints, nh));
+  fn frat_xor_del_bnn(id: u64, lits: *const i64, n: usize, cutoff: i64, out: i64) =>
+    |w| w.del_bnn(id, arr(lits, n), cutoff, out);
+  fn frat_xor_final_bnn(id: u64, lits: *const i64, n: usize, cutoff: i64, out: i64) =>
+    |w| w.finalize_bnn(id, arr(lits, n), cutoff, out);
+  /// Add a clause implied by the BNN `hints[0]`, simplified by the unit clauses `units`.
+  fn frat_xor_bnn_imply(id: u64, lits: *const i64, n: usize,
+    hints: *const i64, nh: usize, units: *const u64, nu: usize) =>
+    |w| w.bnn_imply(id, arr(lits, n), opt_arr(hints, nh), opt_arr(units, nu));
+}
+
+/// Elaborate the proof against the formula at `xnf`, writing XLRUP to `xlrup` (which may be
+/// null to only check it). `flags` is a combination of the `FRAT_XOR_*` flags. Returns 0 on
+/// success and otherwise the exit code `frat-xor elab` would use. The pushed steps are consumed.
+///
+/// # Safety
+/// `h` must be a live handle, and `xnf` and `xlrup` must be null or nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn frat_xor_elaborate(
+  h: *mut FratXor, xnf: *const c_char, xlrup: *const c_char, flags: c_int
+) -> c_int {
+  let (xnf, xlrup) = (path(xnf), path(xlrup));
+  let opts = ElabOptions {
+    full: flags & FRAT_XOR_FULL != 0,
+    validate: flags & (FRAT_XOR_VALIDATE | FRAT_XOR_ALL_HINTS) != 0,
+    all_hints: flags & FRAT_XOR_ALL_HINTS != 0,
+    in_memory: None,
+    verify: flags & FRAT_XOR_VERIFY != 0,
+    comments: flags & FRAT_XOR_COMMENTS != 0,
+    binary: flags & FRAT_XOR_BINARY != 0,
+  };
+  (*h).run(|p| {
+    let proof = p.take().ok_or_else(|| malformed!("the proof has already been elaborated"))?;
+    let xnf = xnf.ok_or_else(|| FratError::from(std::io::Error::new(
+      std::io::ErrorKind::InvalidInput, "missing formula path")))?;
+    match proof.finish()? {
+      Spill::Mem(mem, _) => { elaborate_bytes(mem, Some(xnf), xlrup, &opts)?; }
+      Spill::File(temp, w) => {
+        drop(w); // `finish` has flushed it
+        elaborate(&temp.0, Some(xnf), xlrup, &opts)?;
+      }
+    }
+    Ok(())
+  })
+}
@@ -1,4 +1,6 @@
-use std::convert::TryInto;
+use crate::bnn;
+use crate::error::Result;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Token {
   Nat(i64),
@@ -57,104 +59,209 @@ impl<I: Iterator<Item=u8>> Lexer<I> {
     Comment
   }
 
-  fn scan_xor(&mut self) -> Ident {
-    self.skip_line();
-    Xor
-  }
-
-  fn scan_bnn(&mut self) -> Ident {
-    self.skip_line();
-    Bnn
-  }
-
-  fn scan_keyword(&mut self) -> Ident {
+  fn scan_keyword(&mut self) -> Result<Ident> {
     self.buffer.clear();
     self.buffer.push(self.peek);
-    while (self.bump() as char).is_alphanumeric() {
-      if self.buffer.len() < 3 {
-        self.buffer.push(self.peek);
-      } else { panic!("unknown keyword") }
+    // keywords are alphabetic, so that `x1 2 0` is read as `x 1 2 0`
+    while self.bump().is_ascii_alphabetic() {
+      self.buffer.push(self.peek);
     }
-    match &*self.buffer {
+    Ok(match &*self.buffer {
       b"c"   => self.scan_comment(),
       b"p"   => Problem,
       b"cnf" => Cnf,
       b"d"   => Del,
-      b"x"   => self.scan_xor(),
-      b"b"   => self.scan_bnn(),
-      _      => panic!("unknown keyword")
-    }
+      b"x"   => Xor,
+      b"b"   => Bnn,
+      kw     => return Err(malformed!("unknown keyword '{}'", String::from_utf8_lossy(kw)))
+    })
   }
 
-  fn scan_nat(&mut self) -> i64 {
+  fn scan_nat(&mut self) -> Result<i64> {
     let mut val = (self.peek as char).to_digit(10)
-      .expect("expected a digit to base 10: (0...9)") as i64;
+      .ok_or_else(|| malformed!("expected a digit to base 10: (0...9)"))? as i64;
     while let Some(parsed) = (self.bump() as char).to_digit(10) {
-      val = val.checked_mul(10).unwrap().checked_add(parsed as i64).unwrap();
+      val = val.checked_mul(10).and_then(|val| val.checked_add(parsed as i64))
+        .ok_or_else(|| malformed!("number too large"))?;
     }
-    val
+    Ok(val)
   }
 }
 
 impl<I: Iterator<Item=u8>> Iterator for Lexer<I> {
-  type Item = Token;
+  type Item = Result<Token>;
 
   fn next(&mut self) -> Option<Self::Item> {
     while (self.peek as char).is_whitespace() {
       self.bump();
     }
     if self.peek == 0 { return None; }
-    match self.peek {
+    Some(match self.peek {
       b'a'..=b'z' => match self.scan_keyword() {
-        Comment => self.next(),
-        Xor => self.next(),
-        Bnn => self.next(),
-        tk => Some(Ident(tk))
+        Ok(Comment) => return self.next(),
+        tk => tk.map(Ident)
       },
-      b'0'..=b'9' => Some(Nat(self.scan_nat())),
-      b'-' => { self.bump(); Some(Nat(-self.scan_nat())) },
-      _ => panic!("invalid token start")
-    }
+      b'0'..=b'9' => self.scan_nat().map(Nat),
+      b'-' => { self.bump(); self.scan_nat().map(|n| Nat(-n)) },
+      c => Err(malformed!("invalid token start '{}'", c as char))
+    })
   }
 }
 
 pub type Clause = Box<[i64]>;
 
-pub struct DimacsIter<I>(Lexer<I>);
+/// A constraint of an XNF formula, in input order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+  Clause(Vec<i64>),
+  /// An XOR line `x l_1 ... l_n 0`, as its literal list: the literals sum to 1,
+  /// so the parity is carried by the signs (see `Xor::from_lits`).
+  Xor(Vec<i64>),
+  /// A BNN line `b l_1 ... l_n 0 cutoff [out] 0`, with `out = 0` if omitted.
+  Bnn(bnn::Bnn),
+}
 
-impl<I: Iterator<Item=u8>> DimacsIter<I> {
-  pub fn from(input: I) -> (usize, usize, Self) {
+pub struct XnfIter<I>(Lexer<I>);
+
+impl<I: Iterator<Item=u8>> XnfIter<I> {
+  pub fn from(input: I) -> Result<(usize, usize, Self)> {
     let mut lex = Lexer::from(input);
-    match (lex.next(), lex.next(), lex.next(), lex.next()) {
-      (Some(Ident(Problem)), Some(Ident(Cnf)), Some(Nat(vars)), Some(Nat(clauses))) =>
-        (vars.try_into().unwrap(), clauses.try_into().unwrap(), DimacsIter(lex)),
-      _ => panic!("parse DIMACS failed")
+    match (lex.next().transpose()?, lex.next().transpose()?, lex.next().transpose()?, lex.next().transpose()?) {
+      (Some(Ident(Problem)), Some(Ident(Cnf)), Some(Nat(vars)), Some(Nat(clauses)))
+        if vars >= 0 && clauses >= 0 => Ok((vars as usize, clauses as usize, XnfIter(lex))),
+      _ => Err(malformed!("expected a 'p cnf VARS CONSTRAINTS' header"))
     }
   }
+
+  fn lits(&mut self, mut lits: Vec<i64>) -> Result<Vec<i64>> {
+    loop {
+      match self.0.next().transpose()? {
+        Some(Nat(0)) => return Ok(lits),
+        Some(Nat(lit)) => lits.push(lit),
+        _ => return Err(malformed!("unterminated constraint {:?}", lits))
+      }
+    }
+  }
+
+  fn nat(&mut self) -> Result<i64> {
+    match self.0.next().transpose()? {
+      Some(Nat(n)) => Ok(n),
+      _ => Err(malformed!("expected a number"))
+    }
+  }
+
+  fn constraint(&mut self, tk: Token) -> Result<Constraint> {
+    Ok(match tk {
+      Nat(0) => Constraint::Clause(vec![]),
+      Nat(lit) => Constraint::Clause(self.lits(vec![lit])?),
+      Ident(Xor) => Constraint::Xor(self.lits(vec![])?),
+      Ident(Bnn) => {
+        let lits = self.lits(vec![])?;
+        let cutoff = self.nat()?;
+        let out = match self.nat()? {
+          0 => 0,
+          out => { ensure!(self.nat()? == 0, malformed!("unterminated BNN {:?}", lits)); out }
+        };
+        Constraint::Bnn(bnn::Bnn { lits, cutoff, out })
+      }
+      Ident(tk) => return Err(malformed!("unexpected keyword {:?}", tk))
+    })
+  }
+}
+
+impl<I: Iterator<Item=u8>> Iterator for XnfIter<I> {
+  type Item = Result<Constraint>;
+  fn next(&mut self) -> Option<Result<Constraint>> {
+    Some(self.0.next()?.and_then(|tk| self.constraint(tk)))
+  }
+}
+
+/// Parse an XNF formula, checking it against the `p cnf` header.
+pub fn parse_xnf(input: impl Iterator<Item=u8>) -> Result<(usize, Vec<Constraint>)> {
+  let (vars, count, it) = XnfIter::from(input)?;
+  let mut fmla = Vec::with_capacity(count);
+  for c in it {
+    let c = c?;
+    let lits = match c {
+      Constraint::Clause(ref ls) | Constraint::Xor(ref ls) => ls,
+      Constraint::Bnn(ref bnn) => &bnn.lits,
+    };
+    let out = if let Constraint::Bnn(ref bnn) = c { bnn.out } else { 0 };
+    if let Some(l) = lits.iter().chain(Some(&out)).find(|l| l.unsigned_abs() > vars as u64) {
+      return Err(malformed!("literal {} exceeds the {} variables in the header", l, vars))
+    }
+    fmla.push(c)
+  }
+  ensure!(fmla.len() == count,
+    malformed!("header declares {} constraints, but {} were found", count, fmla.len()));
+  Ok((vars, fmla))
+}
+
+/// Iterates over the clauses of a formula, skipping XORs and BNNs.
+/// Unlike `parse_xnf`, this does not check the formula against its header.
+pub struct DimacsIter<I>(XnfIter<I>);
+
+impl<I: Iterator<Item=u8>> DimacsIter<I> {
+  pub fn from(input: I) -> Result<(usize, usize, Self)> {
+    let (vars, clauses, it) = XnfIter::from(input)?;
+    Ok((vars, clauses, DimacsIter(it)))
+  }
 }
 
 impl<I: Iterator<Item=u8>> Iterator for DimacsIter<I> {
-  type Item = Vec<i64>;
-  fn next(&mut self) -> Option<Vec<i64>> {
-    let mut clause = Vec::new();
+  type Item = Result<Vec<i64>>;
+  fn next(&mut self) -> Option<Result<Vec<i64>>> {
     loop {
       match self.0.next()? {
-        Nat(0) => break,
-        Nat(lit) => clause.push(lit),
-        _ => panic!("parse DIMACS failed")
+        Ok(Constraint::Clause(c)) => return Some(Ok(c)),
+        Ok(_) => {}
+        Err(e) => return Some(Err(e))
       }
     }
-    Some(clause)
   }
 }
 
-pub fn parse_dimacs_map<T>(input: impl Iterator<Item=u8>, f: impl FnMut(Vec<i64>) -> T) -> (usize, Vec<T>) {
-  let (vars, clauses, it) = DimacsIter::from(input);
+pub fn parse_dimacs_map<T>(input: impl Iterator<Item=u8>, mut f: impl FnMut(Vec<i64>) -> T) -> Result<(usize, Vec<T>)> {
+  let (vars, clauses, it) = DimacsIter::from(input)?;
   let mut fmla = Vec::with_capacity(clauses);
-  fmla.extend(it.map(f));
-  (vars, fmla)
+  for c in it { fmla.push(f(c?)) }
+  Ok((vars, fmla))
 }
 
-pub fn parse_dimacs(input: impl Iterator<Item=u8>) -> (usize, Vec<Clause>) {
+pub fn parse_dimacs(input: impl Iterator<Item=u8>) -> Result<(usize, Vec<Clause>)> {
   parse_dimacs_map(input, |x| x.into())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::ErrorKind;
+
+  fn parse(s: &str) -> Result<(usize, Vec<Constraint>)> { parse_xnf(s.bytes()) }
+
+  #[test] fn xnf() {
+    let (vars, fmla) = parse("c comment\np cnf 4 4\n1 -2 0\nx -1 3 0\nb 1 2 3 0 2 4 0\nb 1 -2 0 1 0\n").unwrap();
+    assert_eq!(vars, 4);
+    assert_eq!(fmla, [
+      Constraint::Clause(vec![1, -2]),
+      Constraint::Xor(vec![-1, 3]),
+      Constraint::Bnn(bnn::Bnn::new(&[1, 2, 3], 2, 4)),
+      Constraint::Bnn(bnn::Bnn::new(&[1, -2], 1, 0)),
+    ]);
+  }
+
+  #[test] fn xnf_header() {
+    assert_eq!(parse("p cnf 2 1\nx 1 3 0\n").unwrap_err().cause,
+      "literal 3 exceeds the 2 variables in the header");
+    assert_eq!(parse("p cnf 2 1\nb 1 2 0 1 3 0\n").unwrap_err().cause,
+      "literal 3 exceeds the 2 variables in the header");
+    assert_eq!(parse("p cnf 2 3\n1 0\nx 2 0\n").unwrap_err().cause,
+      "header declares 3 constraints, but 2 were found");
+  }
+
+  #[test] fn xnf_malformed() {
+    let e = parse("p cnf 4 1\nb 1 2 0 1 3 4 0\n").unwrap_err();
+    assert_eq!((e.kind, &*e.cause), (ErrorKind::Malformed, "unterminated BNN [1, 2]"));
+    assert_eq!(parse("p cnf 2 1\ny 1 0\n").unwrap_err().kind, ErrorKind::Malformed);
+  }
+}
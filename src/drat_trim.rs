@@ -1,12 +1,12 @@
 use std::{fmt::{Display, Debug}, io::StdinLock};
-use std::io::{self, Read, Write, Seek, SeekFrom, BufReader};
+use std::io::{self, Read, Write, Seek, BufReader};
 use std::ops::{Deref, DerefMut};
 use std::fs::File;
 use std::mem;
 use std::time::Instant;
 use either::Either;
 use io::{BufRead, BufWriter, stdout};
-use crate::{dimacs, midvec::MidVec, parser::{DRATParser, DRATStep}};
+use crate::{dimacs, midvec::MidVec, parser::{DRATParser, DRATStep}, Result};
 
 const TIMEOUT: u64 = 40000;
 const INIT: usize = 4;
@@ -35,17 +35,14 @@ enum Warning {
   Hard,
 }
 
-#[repr(u8)] #[derive(Copy, Clone, Debug)]
+#[repr(u8)] #[derive(Copy, Clone, Debug, Default)]
 enum Assign {
-  Unassigned,
+  #[default] Unassigned,
   Assigned,
   Assumed,
   Mark,
 }
 
-impl Default for Assign {
-  fn default() -> Self { Self::Unassigned }
-}
 impl Assign {
   #[inline] fn assigned(self) -> bool { !matches!(self, Assign::Unassigned) }
 }
@@ -321,7 +318,7 @@ fn creating(s: &Option<String>, f: impl FnOnce(File) -> io::Result<()>) -> io::R
   if let Some(s) = s { f(File::create(s)?) } else {Ok(())}
 }
 
-macro_rules! reason {($self:ident, $lit:expr) => { $self.reason[$lit.abs() as usize] }}
+macro_rules! reason {($self:ident, $lit:expr) => { $self.reason[$lit.unsigned_abs() as usize] }}
 
 macro_rules! assign {($self:ident, $lit:expr) => {{
   let lit = $lit;
@@ -657,7 +654,7 @@ impl Solver {
       }
       self.print_lrat_line(&mut f, self.count)?;
       f.flush()?;
-      let writes = f.seek(SeekFrom::Current(0))?;
+      let writes = f.stream_position()?;
       if writes != 0 {
         println!("c wrote optimized proof in LRAT format of {} bytes", writes);
       }
@@ -873,8 +870,7 @@ impl Solver {
     loop {
       let p_active = rat_set.iter().map(|&cl| &self.db[cl])
         .filter(|&pr_cls| pr_cls.active()).count();
-      if mem::replace(&mut last_active, Some(p_active))
-          .map_or(false, |last| last >= p_active) {
+      if last_active.replace(p_active).is_some_and(|last| last >= p_active) {
         self.rat_set = rat_set;
         self.witness[w] = witness;
         return true
@@ -1097,6 +1093,7 @@ impl Solver {
 
     let mut active = self.num_clauses;
     let mut adds = 0u64;
+    #[allow(clippy::never_loop)]
     let last_step = 'start_verification: loop {
       for step in 0..self.proof.len() {
         let ad = self.proof[step];
@@ -1255,7 +1252,7 @@ impl Solver {
         println!("s TIMEOUT");
         return Ok(VerifyResult::Fail)
       }
-      if self.bar && adds % 1000 == 0 {
+      if self.bar && adds.is_multiple_of(1000) {
         let time = runtime.as_secs_f64();
         let fraction = 1.0 - adds as f64 / max;
         print!("c {:.2}% [", 100.0 * fraction);
@@ -1372,10 +1369,10 @@ impl Solver {
     }
   }
 
-  fn parse(opts: SolverOpts, input_file: File, proof_file: impl BufRead) -> (bool, Self) {
+  fn parse(opts: SolverOpts, input_file: File, proof_file: impl BufRead) -> Result<(bool, Self)> {
     let mut unsat = false;
     let (num_vars, num_clauses, input_file) =
-      dimacs::DimacsIter::from(BufReader::new(input_file).bytes().map(|c| c.unwrap()));
+      dimacs::DimacsIter::from(BufReader::new(input_file).bytes().map(|c| c.unwrap()))?;
     let mut input_file = input_file.zip(1..);
     let mut formula = Vec::with_capacity(num_clauses);
     println!("c parsing input formula with {} variables and {} clauses", num_vars, num_clauses);
@@ -1400,6 +1397,7 @@ impl Solver {
           file_switch_flag = true;
           continue
         };
+        let clause = clause?;
         for &lit in &clause {
           assert!(lit.abs() <= num_vars as i64,
             "illegal literal {} due to max var {}", lit, num_vars);
@@ -1478,7 +1476,7 @@ impl Solver {
 
     println!("c finished parsing, read {} bytes from proof file", reader.bytes_read());
     let n = max_var as usize;
-    (unsat, Self {
+    Ok((unsat, Self {
       opts,
       count,
       db,
@@ -1509,7 +1507,7 @@ impl Solver {
       num_resolve: 0,
       time: ClauseId(0),
       current: 0,
-    })
+    }))
   }
 }
 
@@ -1546,7 +1544,7 @@ fn print_help() -> ! {
   std::process::exit(0);
 }
 
-pub fn main(mut args: impl Iterator<Item=String>) -> io::Result<()> {
+pub fn main(mut args: impl Iterator<Item=String>) -> Result<()> {
   let mut opts = SolverOpts::new();
   let mut tmp = 0..;
   // input file in DIMACS format
@@ -1586,7 +1584,7 @@ pub fn main(mut args: impl Iterator<Item=String>) -> io::Result<()> {
       match tmp.next().unwrap() {
         0 => input_file = Some(File::open(arg)?),
         1 => {
-          #[allow(clippy::manual_range_contains)]
+          #[allow(clippy::manual_range_contains, clippy::manual_is_ascii_check, clippy::unbuffered_bytes)]
           fn detect_binary(file: &str) -> io::Result<bool> {
             fn ascii(c: u8) -> bool {
               c == b'\r' || c == b' ' || c == b'-' ||
@@ -1626,7 +1624,7 @@ pub fn main(mut args: impl Iterator<Item=String>) -> io::Result<()> {
   }
   opts.reduce &= opts.lrat_file.is_none() && !matches!(opts.mode, Mode::ForwardUnsat);
   opts.full |= matches!(opts.mode, Mode::ForwardUnsat);
-  let (mut unsat, mut s) = Solver::parse(opts, input_file, proof_file);
+  let (mut unsat, mut s) = Solver::parse(opts, input_file, proof_file)?;
   if let Some(proof) = proof_str.filter(|_| s.del_proof) {
     std::fs::remove_file(&proof)?;
     println!("c deleted proof {}", proof);
@@ -4,7 +4,7 @@ use std::fmt;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::fs::{File, read_to_string};
-use std::io::{self, *};
+use std::io::*;
 use super::dimacs::{self, Clause};
 use super::parser::*;
 
@@ -66,6 +66,7 @@ struct Active {
 	hyp: bool
 }
 
+#[derive(Default)]
 pub struct Pass1 {
 	steps: Vec<StepKind2>,
 	active: Vec<Active>
@@ -327,10 +328,10 @@ pub fn process_proof(vars: usize, fmla: &[Clause], drat: ProofIter<impl Iterator
   }
 }
 
-pub fn main(mut args: impl Iterator<Item=String>) -> io::Result<()> {
+pub fn main(mut args: impl Iterator<Item=String>) -> crate::Result<()> {
 	let arg1 = args.next().expect("missing input file");
   let proof = File::open(args.next().expect("missing proof file"))?;
-	let (vars, fmla) = dimacs::parse_dimacs(read_to_string(arg1)?.bytes());
+	let (vars, fmla) = dimacs::parse_dimacs(read_to_string(arg1)?.bytes())?;
 	let drat = ProofIter(BufReader::new(proof).bytes().map(|r| r.expect("read failed")));
 	process_proof(vars, &fmla, drat, match args.next() {
 		None => false,
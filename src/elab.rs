@@ -1,24 +1,40 @@
 #![allow(clippy::iter_with_drain)] // rust-clippy#8538
 
-use std::io::{self, Read, BufReader, Write, BufWriter};
+use std::io::{self, Read, BufReader, Write, BufWriter, Seek, SeekFrom};
 use std::fs::{File, read_to_string};
+use std::path::Path;
 use std::convert::{TryFrom, TryInto};
 use std::mem;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
 use std::ops::{Deref, DerefMut, Index, IndexMut};
 use slab::Slab;
 
 use crate::{HashMap, HashSet};
 use super::midvec::MidVec;
-use super::dimacs::{parse_dimacs, parse_dimacs_map};
+use super::dimacs::{parse_dimacs, parse_xnf, Constraint};
 use super::serialize::{Serialize, ModeWrite, ModeWriter};
 use super::parser::{detect_binary, Step, StepRef, ElabStep, ElabStepRef,
-  AddStep, AddStepRef, Segment, Proof, Mode, Ascii, Bin, DefaultMode, LRATParser, LRATStep};
-use super::backparser::{VecBackParser, BackParser, StepIter, ElabStepIter};
+  AddStep, AddStepRef, Segment, Proof, Mode, Ascii, Bin, DefaultMode, LRATParser, LRATStep,
+  XLRUPParser, XLRUPStep, XLRUPStepRef};
+use super::backparser::{VecBackParser, BackParser, Segments, StepIter, ElabStepIter};
 use super::perm_clause::*;
+use super::xor::Xor;
+use super::gauss::Gauss;
+use super::error::{FratError, Result, usage_error};
+use super::bnn::Bnn;
+use super::json::Obj;
 
 // Set this to true to get an error log when unit propagation fails (assumes no RAT steps)
 const LOG_UNIT_PROP_ERROR: bool = false;
 
+// XOR-from-clause steps enumerate all 2^n assignments to the XOR, so wider XORs are rejected
+const MAX_CLAUSAL_XOR: usize = 20;
+
+// Clauses that elab derives from XORs get IDs counting down from here, which solvers
+// are not expected to reach (signed IDs are written as twice their value in binary).
+const XOR_REASON_IDS: u64 = 1 << 62;
+
 #[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 struct Reason(usize);
 
@@ -28,13 +44,10 @@ impl Reason {
   fn clause(self) -> Option<usize> { self.0.checked_sub(1) }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 #[repr(u8)]
-enum Assign { No = 0, Yes = 1, Mark = 2 }
+enum Assign { #[default] No = 0, Yes = 1, Mark = 2 }
 
-impl Default for Assign {
-  fn default() -> Self { Self::No }
-}
 impl Assign {
   #[inline] fn assigned(self) -> bool { self != Self::No }
 }
@@ -148,10 +161,10 @@ impl IndexMut<usize> for Clause {
 }
 
 impl Clause {
-  fn check_subsumed(&self, lits: &[i64], step: u64) {
-    assert!(lits.iter().all(|lit| self.contains(lit)),
-      "at {:?}: Clause {:?} added here will later be deleted as {:?}",
-      step, self, lits)
+  fn check_subsumed(&self, lits: &[i64]) -> Result<()> {
+    ensure!(lits.iter().all(|lit| self.contains(lit)),
+      invalid!("clause {:?} added here will later be deleted as {:?}", self, lits));
+    Ok(())
   }
 
   fn max_var(&self) -> i64 {
@@ -159,6 +172,52 @@ impl Clause {
   }
 }
 
+#[derive(Debug)]
+struct LiveXor {
+  marked: bool,
+  xor: Xor,
+  /// The variables watched by `xor_reasons`, or `[0, 0]` if there are fewer than two.
+  watch: [i64; 2],
+}
+
+impl LiveXor {
+  fn check_same(&self, lits: &[i64]) -> Result<()> {
+    let x = Xor::from_lits(lits);
+    ensure!(self.xor == x, invalid!("XOR {:?} added here will later be deleted as {:?}", x, self.xor));
+    Ok(())
+  }
+}
+
+/// A clause-from-XOR step waiting for the contents of XORs that are never deleted or finalized.
+#[derive(Debug)]
+struct PendingImply {
+  i: u64,
+  offset: usize,
+  ls: Vec<i64>,
+  hint: Vec<i64>,
+  /// The sum of the XORs in `hint` known so far.
+  sum: Xor,
+  /// The number of XORs in `hint` still to be added to `sum`.
+  unknown: usize,
+}
+
+#[derive(Debug)]
+struct LiveBnn {
+  marked: bool,
+  bnn: Bnn,
+}
+
+impl LiveBnn {
+  fn check_same(&self, lits: &[i64], cutoff: i64, out: i64) -> Result<()> {
+    let (mut a, mut b) = (lits.to_vec(), self.bnn.lits.clone());
+    a.sort_unstable();
+    b.sort_unstable();
+    ensure!(a == b && cutoff == self.bnn.cutoff && out == self.bnn.out,
+      invalid!("BNN {:?} >= {} <-> {} added here will later be deleted as {:?}", lits, cutoff, out, self.bnn));
+    Ok(())
+  }
+}
+
 #[derive(Default)]
 struct Watches([MidVec<Vec<usize>>; 2]);
 
@@ -206,18 +265,37 @@ struct Context {
   clauses: Slab<Clause>,
   names: HashMap<u64, usize>,
   units: HashMap<usize, i64>,
+  /// The live unit clauses asserting each literal, for `unit_for`.
+  unit_ids: HashMap<i64, Vec<u64>>,
+  xors: HashMap<u64, LiveXor>,
+  bnns: HashMap<u64, LiveBnn>,
   watch: Watches,
   va: VAssign,
   clauses_by_maxvar: Option<Vec<HashSet<usize>>>,
   rat_set_lit: i64,
   step: u64,
-  /// True if invalid hints should be rejected.
+  /// True if invalid hints, and XORs and BNNs that are never deleted or finalized, should be rejected.
   validate_hints: bool,
   /// True if invalid or missing hints should be rejected. (Implies `validate_hints`)
   all_hints: bool,
   /// True if in LRAT mode (implies `all_hints`)
   lrat: bool,
   full: bool,
+  /// True if unit propagation may also use the live XORs (see `propagate_xor`).
+  xor_rup: bool,
+  /// The clauses added by `propagate_xor` since the last step, with the XOR implying each.
+  xor_reasons: Vec<(u64, u64)>,
+  /// The number of clauses added by `propagate_xor` so far.
+  xor_reasons_made: u64,
+  /// The live XORs watching each variable. XORs over fewer than two variables watch
+  /// variable 0, which `xor_reasons` visits every time.
+  xor_watch: HashMap<i64, Vec<u64>>,
+  /// The XORs cited by clause-from-XOR steps without being live there, because they are
+  /// never deleted or finalized. Each has a mark and the steps in `pending_implies` citing it.
+  implicit_xors: HashMap<u64, (bool, Vec<usize>)>,
+  pending_implies: Vec<PendingImply>,
+  /// The XOR or BNN that the failing step did not find live, for `never_finalized`.
+  missing: Cell<Option<(&'static str, u64)>>,
 }
 
 fn dedup_vec<T: PartialEq>(vec: &mut Vec<T>) {
@@ -231,8 +309,42 @@ fn dedup_vec<T: PartialEq>(vec: &mut Vec<T>) {
   }
 }
 
+/// The clause by which `xor` propagates or conflicts under `va`, if it does.
+/// A propagated literal comes first.
+fn xor_reason(va: &VAssign, xor: &Xor) -> Option<Box<[i64]>> {
+  let n = va.tru_lits.len();
+  let (mut rhs, mut free, mut lits) = (xor.rhs, None, vec![]);
+  for &v in &xor.vars {
+    if v <= n && va.is_true(v) { rhs = !rhs; lits.push(-v) }
+    else if v <= n && va.is_false(v) { lits.push(v) }
+    else if free.replace(v).is_some() { return None }
+  }
+  if let Some(v) = free { lits.insert(0, if rhs {v} else {-v}) }
+  else if !rhs { return None }
+  Some(lits.into())
+}
+
+// The clause `ls` follows from an XOR if the assignment falsifying `ls`
+// fixes every variable of the XOR and violates it.
+fn xor_implies(ls: &[i64], sum: &Xor, hint: &[i64]) -> Result<()> {
+  // A repeated literal must only count once in the parity below.
+  let mut ls = ls.to_vec();
+  ls.sort_unstable();
+  ls.dedup();
+  if ls.iter().any(|l| ls.contains(&-l)) { return Ok(()) }
+  let missing: Vec<i64> = sum.vars.iter().copied()
+    .filter(|&v| !ls.contains(&v) && !ls.contains(&-v)).collect();
+  ensure!(missing.is_empty(), invalid!("clause {:?} does not mention variables {:?} \
+    of the sum {:?} of XORs {:?}", ls, missing, sum, hint));
+  let value = ls.iter().filter(|&&l| l < 0 && sum.vars.contains(&-l)).count() % 2 != 0;
+  ensure!(value != sum.rhs, invalid!("the assignment {:?} falsifying clause {:?} \
+    satisfies the sum {:?} of XORs {:?}",
+    ls.iter().map(|&l| -l).collect::<Vec<_>>(), ls, sum, hint));
+  Ok(())
+}
+
 fn trim_cbm(cbm: &mut Vec<HashSet<usize>>) -> i64 {
-  while cbm.last().map_or(false, |set| set.is_empty()) { cbm.pop(); }
+  while cbm.last().is_some_and(|set| set.is_empty()) { cbm.pop(); }
   cbm.len() as i64
 }
 
@@ -272,16 +384,16 @@ impl Context {
     self.va.reserve_to(self.max_var);
   }
 
-  #[inline] fn insert(&mut self, name: u64, marked: bool, lits: Box<[i64]>) {
+  #[inline] fn insert(&mut self, name: u64, marked: bool, lits: Box<[i64]>) -> Result<()> {
     self.reserve(&lits);
-    self.insert_no_reserve(name, marked, lits);
+    self.insert_no_reserve(name, marked, lits)
   }
 
-  fn insert_no_reserve(&mut self, name: u64, marked: bool, mut lits: Box<[i64]>) {
+  fn insert_no_reserve(&mut self, name: u64, marked: bool, mut lits: Box<[i64]>) -> Result<()> {
+    ensure!(!self.names.contains_key(&name), invalid!("clause {} to be inserted already exists", name));
     let unit = self.sort_unit(&mut lits);
     let i = self.clauses.insert(Clause {marked, name, lits});
-    assert!(self.names.insert(name, i).is_none(),
-      "at {:?}: Clause {} to be inserted already exists", self.step, name);
+    self.names.insert(name, i);
     if let Some(ref mut cbm) = self.clauses_by_maxvar {
       if let Some(maxvar) = usize::try_from(self.clauses[i].max_var()).unwrap().checked_sub(1) {
         while maxvar >= cbm.len() { cbm.push(Default::default()); }
@@ -296,16 +408,19 @@ impl Context {
       self.watch.add(marked, l1, i);
       self.watch.add(marked, l2, i);
     } else {
-      assert!(self.units.insert(i, lits.first().copied().unwrap_or(0)).is_none())
+      let l = lits.first().copied().unwrap_or(0);
+      assert!(self.units.insert(i, l).is_none());
+      self.unit_ids.entry(l).or_default().push(name);
     }
     if !self.all_hints && unit && self.va.unsat().is_none() {
       self.va.add_unit(lits.first().copied().unwrap_or(0), i);
     }
+    Ok(())
   }
 
-  fn remove(&mut self, name: u64) -> Clause {
-    let i = self.names.remove(&name).unwrap_or_else(
-      || panic!("at {:?}: Clause {} to be removed does not exist", self.step, name));
+  fn remove(&mut self, name: u64) -> Result<Clause> {
+    let i = self.names.remove(&name).ok_or_else(
+      || invalid!("clause {} to be removed does not exist", name))?;
 
     let cl = &self.clauses[i];
     if let Some(ref mut cbm) = self.clauses_by_maxvar {
@@ -331,13 +446,16 @@ impl Context {
       }
     } else {
       self.va.unassign(cl.first().copied().unwrap_or(0));
-      self.units.remove(&i).expect("unit not found");
+      let l = self.units.remove(&i).expect("unit not found");
+      let ids = self.unit_ids.get_mut(&l).expect("unit not found");
+      ids.swap_remove(ids.iter().position(|&j| j == cl.name).expect("unit not found"));
+      if ids.is_empty() { self.unit_ids.remove(&l); }
     }
 
-    self.clauses.remove(i)
+    Ok(self.clauses.remove(i))
   }
 
-  fn reloc(&mut self, relocs: &mut Vec<(u64, u64)>) {
+  fn reloc(&mut self, relocs: &mut Vec<(u64, u64)>) -> Result<()> {
     let mut m = HashMap::default();
     let mut removed = Vec::new();
     relocs.retain(|&(from, to)| {
@@ -348,15 +466,350 @@ impl Context {
       } else {false}
     });
     for (from, addr) in removed {
+      if let Some(&l) = self.units.get(&addr) {
+        let ids = self.unit_ids.get_mut(&l).expect("unit not found");
+        let to = self.clauses[addr].name;
+        *ids.iter_mut().find(|j| **j == to).expect("unit not found") = from;
+      }
       self.clauses[addr].name = from;
-      assert!(self.names.insert(from, addr).is_none(),
-        "at {:?}: Clause {} to be inserted already exists", self.step, from);
+      ensure!(self.names.insert(from, addr).is_none(),
+        invalid!("clause {} to be inserted already exists", from));
+    }
+    Ok(())
+  }
+
+  fn get(&self, i: u64) -> Result<usize> {
+    self.names.get(&i).copied().ok_or_else(
+      || invalid!("clause {} to be accessed does not exist", i))
+  }
+
+  fn insert_xor(&mut self, name: u64, marked: bool, lits: &[i64]) -> Result<()> {
+    ensure!(!self.implicit_xors.contains_key(&name),
+      invalid!("XOR {} is deleted here but used later in the proof", name));
+    let xor = Xor::from_lits(lits);
+    let watch = match *xor.vars { [v1, v2, ..] => [v1, v2], _ => [0, 0] };
+    ensure!(self.xors.insert(name, LiveXor {marked, xor, watch}).is_none(),
+      invalid!("XOR {} to be inserted already exists", name));
+    self.xor_watch.entry(watch[0]).or_default().push(name);
+    if watch[1] != watch[0] { self.xor_watch.entry(watch[1]).or_default().push(name) }
+    Ok(())
+  }
+
+  /// Make the XOR `name` live at a step deleting or finalizing it as `ls`.
+  fn undel_xor(&mut self, name: u64, ls: &[i64]) -> Result<()> {
+    ensure!(!self.xors.contains_key(&name),
+      invalid!("XOR {} is deleted or finalized again later in the proof", name));
+    self.insert_xor(name, false, ls)
+  }
+
+  fn remove_xor(&mut self, name: u64) -> Result<LiveXor> {
+    let x = self.xors.remove(&name).ok_or_else(|| invalid!("XOR {} to be removed does not exist", name))?;
+    for v in x.watch {
+      if let Some(ws) = self.xor_watch.get_mut(&v) {
+        if let Some(i) = ws.iter().position(|&y| y == name) { ws.swap_remove(i); }
+      }
+    }
+    Ok(x)
+  }
+
+  /// Remove the XOR `name` at the step introducing it as `ls`. An XOR that is never deleted
+  /// or finalized is not live, and is only needed by the clause-from-XOR steps waiting for it.
+  fn intro_xor(&mut self, name: u64, ls: &[i64]) -> Result<LiveXor> {
+    let xor = Xor::from_lits(ls);
+    ensure!(!self.validate_hints || self.xors.contains_key(&name),
+      invalid!("XOR {} is never deleted or finalized", name));
+    if let Some((marked, pending)) = self.implicit_xors.remove(&name) {
+      for k in pending {
+        let p = &mut self.pending_implies[k];
+        p.sum.add(&xor);
+        p.unknown -= 1;
+        if p.unknown == 0 {
+          xor_implies(&p.ls, &p.sum, &p.hint).map_err(|e| e.at("imply", Some(p.i)).offset(Some(p.offset)))?
+        }
+      }
+      return Ok(LiveXor {marked, xor, watch: [0, 0]})
+    }
+    if !self.xors.contains_key(&name) { return Ok(LiveXor {marked: false, xor, watch: [0, 0]}) }
+    let x = self.remove_xor(name)?;
+    x.check_same(ls)?;
+    Ok(x)
+  }
+
+  fn get_xor(&self, name: u64) -> Result<&Xor> {
+    self.xors.get(&name).map(|x| &x.xor).ok_or_else(|| {
+      self.missing.set(Some(("XOR", name)));
+      invalid!("XOR {} to be accessed does not exist", name)
+    })
+  }
+
+  /// Mark the XORs in `hint`, calling `newly` on the ones that were not marked yet.
+  fn mark_xors(&mut self, hint: &[i64], mut newly: impl FnMut(u64) -> io::Result<()>) -> Result<()> {
+    let missing = &self.missing;
+    for &x in hint {
+      let x = x.unsigned_abs();
+      let marked = match self.xors.get_mut(&x) {
+        Some(lx) => &mut lx.marked,
+        None => &mut self.implicit_xors.get_mut(&x).ok_or_else(|| {
+          missing.set(Some(("XOR", x)));
+          invalid!("XOR {} to be accessed does not exist", x)
+        })?.0,
+      };
+      if !*marked { *marked = true; newly(x)? }
+    }
+    Ok(())
+  }
+
+  // Mark clause `i` as needed by the elaborated proof, watching it for unit propagation
+  // from now on; `newly` is called the first time.
+  fn need_clause(&mut self, i: u64, newly: impl FnOnce(u64) -> io::Result<()>) -> Result<()> {
+    let c = self.get(i)?;
+    let cl = &mut self.clauses[c];
+    if !cl.marked { // If the necessary clause is not active yet
+      cl.marked = true; // Make it active
+      if let [a, b, ..] = *cl.lits {
+        self.watch.del(false, a, c);
+        self.watch.del(false, b, c);
+        self.watch.add(true, a, c);
+        self.watch.add(true, b, c);
+      }
+      newly(i)?
+    }
+    Ok(())
+  }
+
+  fn insert_bnn(&mut self, name: u64, marked: bool, bnn: Bnn) -> Result<()> {
+    ensure!(self.bnns.insert(name, LiveBnn {marked, bnn}).is_none(),
+      invalid!("BNN {} to be inserted already exists", name));
+    Ok(())
+  }
+
+  /// Make the BNN `name` live at a step deleting or finalizing it.
+  fn undel_bnn(&mut self, name: u64, bnn: Bnn) -> Result<()> {
+    ensure!(!self.bnns.contains_key(&name),
+      invalid!("BNN {} is deleted or finalized again later in the proof", name));
+    self.insert_bnn(name, false, bnn)
+  }
+
+  fn remove_bnn(&mut self, name: u64) -> Result<LiveBnn> {
+    self.bnns.remove(&name).ok_or_else(|| invalid!("BNN {} to be removed does not exist", name))
+  }
+
+  /// Remove the BNN `name` at the step introducing it. A BNN that is never deleted
+  /// or finalized is not live, and is never used.
+  fn intro_bnn(&mut self, name: u64, ls: &[i64], rhs: i64, out: i64) -> Result<LiveBnn> {
+    if !self.bnns.contains_key(&name) {
+      ensure!(!self.validate_hints, invalid!("BNN {} is never deleted or finalized", name));
+      return Ok(LiveBnn {marked: false, bnn: Bnn::new(ls, rhs, out)})
+    }
+    let b = self.remove_bnn(name)?;
+    b.check_same(ls, rhs, out)?;
+    Ok(b)
+  }
+
+  fn get_bnn(&self, name: u64) -> Result<&Bnn> {
+    self.bnns.get(&name).map(|b| &b.bnn).ok_or_else(|| {
+      self.missing.set(Some(("BNN", name)));
+      invalid!("BNN {} to be accessed does not exist", name)
+    })
+  }
+
+  /// Mark the BNN `name`, calling `newly` if it was not marked yet.
+  fn mark_bnn(&mut self, name: u64, newly: impl FnOnce(u64) -> io::Result<()>) -> Result<()> {
+    let missing = &self.missing;
+    let lb = self.bnns.get_mut(&name).ok_or_else(|| {
+      missing.set(Some(("BNN", name)));
+      invalid!("BNN {} to be accessed does not exist", name)
+    })?;
+    if !lb.marked { lb.marked = true; newly(name)? }
+    Ok(())
+  }
+
+  // Add up the XORs in `hint`, then substitute the unit clauses in `units`.
+  fn xor_sum(&self, hint: &[i64], units: &[u64]) -> Result<Xor> {
+    let mut sum = Xor::default();
+    for &x in hint { sum.add(self.get_xor(x.unsigned_abs())?) }
+    for &u in units {
+      match *self.clauses[self.get(u)?] {
+        [l] => sum.assign(l),
+        ref lits => return Err(invalid!("unit hint {} is not a unit clause: {:?}", u, lits))
+      }
+    }
+    Ok(sum)
+  }
+
+  /// Find live XORs whose sum, after making the literals `assign` true, is `target`.
+  fn gauss_hint(&self, target: &Xor, assign: &[i64]) -> Option<Vec<i64>> {
+    let mut g = Gauss::default();
+    for (&i, x) in &self.xors {
+      let mut x = x.xor.clone();
+      for &l in assign { x.assign(l) }
+      g.insert(i, x)
+    }
+    Some(g.solve(target)?.into_iter().map(|i| i as i64).collect())
+  }
+
+  /// The hint for an XOR addition step without one.
+  fn find_xor_add_hint(&self, ls: &[i64], units: &[u64]) -> Result<Vec<i64>> {
+    let mut assign = vec![];
+    for &u in units {
+      match *self.clauses[self.get(u)?] {
+        [l] => assign.push(l),
+        ref lits => return Err(invalid!("unit hint {} is not a unit clause: {:?}", u, lits))
+      }
+    }
+    let x = Xor::from_lits(ls);
+    self.gauss_hint(&x, &assign).ok_or_else(|| invalid!("XOR {:?} is not a sum of live XORs", x))
+  }
+
+  /// The hint for a clause-from-XOR step without one: XORs whose sum is violated by
+  /// the assignment falsifying `ls`, and has no other variables.
+  fn find_xor_imply_hint(&self, ls: &[i64]) -> Result<Vec<i64>> {
+    if ls.iter().any(|l| ls.contains(&-l)) { return Ok(vec![]) }
+    let falsify: Vec<i64> = ls.iter().map(|&l| -l).collect();
+    self.gauss_hint(&Xor { vars: vec![], rhs: true }, &falsify)
+      .ok_or_else(|| invalid!("clause {:?} is not implied by a sum of live XORs", ls))
+  }
+
+  /// A live unit clause asserting `l`, looked up on the trail or else among the unit clauses.
+  fn unit_for(&self, l: i64) -> Option<u64> {
+    if l.abs() <= self.va.tru_lits.len() && self.va.is_true(l) {
+      if let Some(c) = self.va.reasons[l].clause() {
+        if *self.clauses[c] == [l] { return Some(self.clauses[c].name) }
+      }
+    }
+    self.unit_ids.get(&l).and_then(|ids| ids.first()).copied()
+  }
+
+  /// The unit clauses needed to turn the sum of the XORs in `hint` into `ls`:
+  /// one for each variable on which they differ. Variables without a live unit clause
+  /// are left out, for `check_xor_add` to report.
+  fn xor_add_units(&self, ls: &[i64], hint: &[i64]) -> Result<Vec<u64>> {
+    let sum = self.xor_sum(hint, &[])?;
+    Ok(Xor::from_lits(ls).diff(&sum).into_iter()
+      .filter_map(|v| self.unit_for(v).or_else(|| self.unit_for(-v))).collect())
+  }
+
+  /// A minimal set of unit clauses that, together with falsifying `ls`, violates the BNN `b`.
+  /// If there is none, all the units on its literals are returned, for `check_bnn_imply` to report.
+  fn bnn_imply_units(&self, ls: &[i64], b: u64) -> Result<Vec<u64>> {
+    let bnn = self.get_bnn(b)?;
+    let mut tru: HashSet<i64> = ls.iter().map(|&l| -l).collect();
+    if tru.iter().any(|l| tru.contains(&-l)) { return Ok(vec![]) }
+    let mut units = vec![];
+    for &l in bnn.lits.iter().chain(Some(&bnn.out).filter(|&&o| o != 0)) {
+      if tru.contains(&l) || tru.contains(&-l) { continue }
+      for l in [l, -l] {
+        if let Some(u) = self.unit_for(l) { tru.insert(l); units.push((u, l)); break }
+      }
+    }
+    if !bnn.violated(|l| tru.contains(&l)) {
+      return Ok(units.into_iter().map(|p| p.0).collect())
     }
+    // Violation is preserved by extending the assignment, so dropping
+    // units one at a time while it holds leaves a minimal set.
+    units.retain(|&(_, l)| {
+      tru.remove(&l);
+      let needed = !bnn.violated(|l| tru.contains(&l));
+      if needed { tru.insert(l); }
+      needed
+    });
+    Ok(units.into_iter().map(|p| p.0).collect())
+  }
+
+  fn check_xor_add(&self, ls: &[i64], hint: &[i64], units: &[u64]) -> Result<()> {
+    let (x, sum) = (Xor::from_lits(ls), self.xor_sum(hint, units)?);
+    if x != sum {
+      return Err(match x.diff(&sum) {
+        d if d.is_empty() => invalid!("XOR {:?} differs in parity \
+          from the sum {:?} of XORs {:?}", x, sum, hint),
+        d => invalid!("XOR {:?} differs from the sum {:?} of XORs {:?} \
+          on variables {:?}", x, sum, hint, d),
+      })
+    }
+    Ok(())
   }
 
-  fn get(&self, i: u64) -> usize {
-    *self.names.get(&i).unwrap_or_else(
-      || panic!("at {:?}: Clause {} to be accessed does not exist", self.step, i))
+  fn check_xor_imply(&self, ls: &[i64], hint: &[i64]) -> Result<()> {
+    xor_implies(ls, &self.xor_sum(hint, &[])?, hint)
+  }
+
+  /// Like `check_xor_imply` for the clause-from-XOR step `i` at byte `offset`, but the XORs
+  /// in `hint` that are not live here may be left live to the end of the proof without being
+  /// finalized. Their contents are only known once the proof introduces them, further back,
+  /// so the check waits until `intro_xor` has seen them all.
+  fn check_xor_imply_later(&mut self, i: u64, offset: usize, ls: &[i64], hint: &[i64]) -> Result<()> {
+    let (mut sum, mut unknown) = (Xor::default(), vec![]);
+    for &x in hint {
+      match self.xors.get(&x.unsigned_abs()) {
+        Some(lx) => sum.add(&lx.xor),
+        None => unknown.push(x.unsigned_abs()),
+      }
+    }
+    if unknown.is_empty() { return xor_implies(ls, &sum, hint) }
+    let k = self.pending_implies.len();
+    for &x in &unknown { self.implicit_xors.entry(x).or_default().1.push(k) }
+    let (ls, hint) = (ls.to_vec(), hint.to_vec());
+    self.pending_implies.push(PendingImply {i, offset, ls, hint, sum, unknown: unknown.len()});
+    Ok(())
+  }
+
+  // The XOR `ls` follows from the clauses in `hint` if every assignment to its variables
+  // that violates it falsifies one of the clauses. This enumerates all 2^(n-1) such
+  // assignments, which is fine for the short XORs that solvers recover from clauses.
+  fn check_clauses_imply_xor(&self, ls: &[i64], hint: &[i64]) -> Result<()> {
+    let x = Xor::from_lits(ls);
+    ensure!(x.vars.len() <= MAX_CLAUSAL_XOR, invalid!("XOR {:?} has more than {} variables, \
+      too many to check against clauses", x, MAX_CLAUSAL_XOR));
+    // A clause is falsified by the assignments (as bitmasks over x.vars)
+    // that agree with `vals` on `care`; clauses with other variables never are.
+    let mut blocks: Vec<(u64, u64)> = vec![];
+    'next: for &c in hint {
+      let (mut care, mut vals) = (0u64, 0u64);
+      for &l in &*self.clauses[self.get(c.unsigned_abs())?] {
+        let i = match x.vars.binary_search(&l.abs()) { Ok(i) => i, Err(_) => continue 'next };
+        let bit = 1 << i;
+        if care & bit != 0 && (vals & bit != 0) != (l < 0) { continue 'next }
+        care |= bit;
+        if l < 0 { vals |= bit }
+      }
+      blocks.push((care, vals))
+    }
+    let (mut misses, mut missed) = (0u64, vec![]);
+    for a in 0..1u64 << x.vars.len() {
+      if (a.count_ones() % 2 != 0) != x.rhs &&
+          !blocks.iter().any(|&(care, vals)| a & care == vals) {
+        misses += 1;
+        if missed.len() < 4 {
+          missed.push(x.vars.iter().enumerate()
+            .map(|(i, &v)| if a & 1 << i != 0 {v} else {-v}).collect::<Vec<_>>())
+        }
+      }
+    }
+    ensure!(misses == 0, invalid!("clauses {:?} do not rule out \
+      {} assignments violating XOR {:?}, including {:?}", hint, misses, x, missed));
+    Ok(())
+  }
+
+  // The clause `ls` follows from a BNN if the BNN is violated after
+  // setting the unit clauses `units` and falsifying `ls`.
+  fn check_bnn_imply(&self, ls: &[i64], hint: &[i64], units: &[u64]) -> Result<()> {
+    let b = match *hint {
+      [b] => b.unsigned_abs(),
+      _ => return Err(malformed!("expected a single BNN hint, got {:?}", hint))
+    };
+    let mut tru: HashSet<i64> = ls.iter().map(|&l| -l).collect();
+    for &u in units {
+      match *self.clauses[self.get(u)?] {
+        [l] => { tru.insert(l); }
+        ref lits => return Err(invalid!("unit hint {} is not a unit clause: {:?}", u, lits))
+      }
+    }
+    if tru.iter().any(|l| tru.contains(&-l)) { return Ok(()) }
+    let bnn = self.get_bnn(b)?;
+    ensure!(bnn.violated(|l| tru.contains(&l)), invalid!("BNN {} {:?} is not \
+      violated by units {:?} and the assignment {:?} falsifying clause {:?}", b, bnn,
+      units, ls.iter().map(|&l| -l).collect::<Vec<_>>(), ls));
+    Ok(())
   }
 
   fn finalize_hint(&mut self, conflict: i64, hint: &mut Hint) {
@@ -574,6 +1027,76 @@ impl Context {
     None
   }
 
+  /// The clauses by which the live XORs propagate or conflict under the current assignment,
+  /// each with the XOR implying it. A propagated literal comes first.
+  ///
+  /// Only the XORs watching an assigned variable not yet in `done` are visited. Their watches
+  /// are moved to unassigned variables where possible, and the rest propagate or conflict.
+  fn xor_reasons(&mut self, done: &mut HashSet<i64>) -> Vec<(u64, Box<[i64]>)> {
+    let Self {va, xors, xor_watch, ..} = self;
+    let n = va.tru_lits.len();
+    let assigned = |v: i64| v <= n && (va.is_true(v) || va.is_false(v));
+    let (mut seen, mut moved, mut out) = (HashSet::default(), vec![], vec![]);
+    for v in std::iter::once(0).chain(va.tru_stack.iter().map(|l| l.abs())) {
+      if v != 0 && !done.insert(v) { continue }
+      let Some(ws) = xor_watch.get_mut(&v) else { continue };
+      let mut j = 0;
+      while let Some(&x) = ws.get(j) {
+        let lx = xors.get_mut(&x).expect("watching XOR not found");
+        if v != 0 {
+          let i = (lx.watch[1] == v) as usize;
+          let other = lx.watch[1 - i];
+          if let Some(&w) = lx.xor.vars.iter().find(|&&w| w != other && !assigned(w)) {
+            lx.watch[i] = w;
+            moved.push((w, ws.swap_remove(j)));
+            continue
+          }
+        }
+        j += 1;
+        if seen.insert(x) {
+          if let Some(lits) = xor_reason(va, &lx.xor) { out.push((x, lits)) }
+        }
+      }
+      for (w, x) in moved.drain(..) { xor_watch.entry(w).or_default().push(x) }
+    }
+    out
+  }
+
+  /// Delete the clauses added by `propagate_xor` for the current step.
+  fn remove_xor_reasons(&mut self) -> Result<()> {
+    self.va.clear_hyps();
+    for (j, _) in mem::take(&mut self.xor_reasons) { self.remove(j)?; }
+    Ok(())
+  }
+
+  /// Like `propagate`, but when unit propagation gets stuck, add the clauses by which
+  /// the live XORs propagate or conflict (recording them in `xor_reasons`) and retry.
+  fn propagate_xor(&mut self, c: &[i64]) -> Result<Option<i64>> {
+    // The assignment only grows from one round to the next, so the XORs watching
+    // a variable need to be visited once
+    let mut done = HashSet::default();
+    loop {
+      if let Some(k) = self.propagate(c) { return Ok(Some(k)) }
+      let reasons = self.xor_reasons(&mut done);
+      if reasons.is_empty() { break }
+      self.va.clear_hyps();
+      for (x, lits) in reasons {
+        let name = XOR_REASON_IDS - self.xor_reasons_made;
+        self.xor_reasons_made += 1;
+        self.insert(name, true, lits)?;
+        self.xor_reasons.push((name, x));
+      }
+    }
+    // The clauses from the XORs did not help, so take them out again before
+    // the RAT fallback, and redo the assignment it starts from
+    if !self.xor_reasons.is_empty() {
+      self.remove_xor_reasons()?;
+      let k = self.propagate(c);
+      debug_assert!(k.is_none());
+    }
+    Ok(None)
+  }
+
   #[allow(unused)]
   fn log_status(&self, file: &str, c: &[i64]) -> io::Result<()> {
     let Self {va, clauses, units, ..} = self;
@@ -628,27 +1151,27 @@ impl Context {
     log.flush()
   }
 
-  fn propagate_hint(&mut self, ls: &[i64], is: &[i64]) -> Option<i64> {
+  fn propagate_hint(&mut self, ls: &[i64], is: &[i64]) -> Result<Option<i64>> {
     // if verb {
     //   println!("propagate_hint {:?} {:?}", ls, is);
     //   let _ = self.log_status("unit_prop_before.log", ls);
     // }
 
-    if let Some(k) = self.va.unsat() { return Some(k) }
+    if let Some(k) = self.va.unsat() { return Ok(Some(k)) }
 
     if !self.all_hints && !self.va.units_processed {
       for (&c, &l) in &self.units {
         self.va.add_unit(l, c);
-        if let Some(k) = self.va.unsat() { return Some(k) }
+        if let Some(k) = self.va.unsat() { return Ok(Some(k)) }
       }
       self.va.units_processed = true;
     }
 
     for &x in ls {
-      if !self.va.assign(-x, Reason::NONE) { return Some(x) }
+      if !self.va.assign(-x, Reason::NONE) { return Ok(Some(x)) }
     }
 
-    let mut is: Vec<usize> = is.iter().map(|&i| self.get(i as u64)).collect();
+    let mut is: Vec<usize> = is.iter().map(|&i| self.get(i as u64)).collect::<Result<_>>()?;
     let Context {va, clauses, watch, ..} = self;
     let mut queue = vec![];
     loop {
@@ -662,7 +1185,7 @@ impl Context {
         let unsat = if let Some(i) = (1..cl.len()).find(|&i| !va.is_false(cl[i])) {
           let l = cl[0];
           if !va.is_false(l) || cl.lits[i+1..].iter().any(|&l| !va.is_false(l)) {
-            assert!(!self.validate_hints, "at {:?}: clause {:?} is not unit", self.step, cl.name);
+            ensure!(!self.validate_hints, invalid!("clause {:?} is not unit", cl.name));
             queue.push(c);
             continue
           }
@@ -679,46 +1202,45 @@ impl Context {
           k = 0; true
         };
         assert!(va.assign(k, Reason::new(c)) != unsat);
-        if unsat { return Some(k) }
+        if unsat { return Ok(Some(k)) }
         progress = true;
       }
-      if !progress { return None }
+      if !progress { return Ok(None) }
       mem::swap(&mut is, &mut queue);
     }
   }
 
-  fn build_step(&mut self, ls: &[i64], hint: Option<&[i64]>, out: &mut Hint,
+  fn build_step(&mut self, ls: &[i64], hint: Option<&[i64]>, out: &mut Hint, xor: bool,
     fallback: impl FnOnce(&mut Self) -> Option<()>,
-  ) -> bool {
+  ) -> Result<bool> {
     if let Some(is) = hint {
-      if let Some(k) = self.propagate_hint(ls, is) {
+      if let Some(k) = self.propagate_hint(ls, is)? {
         self.finalize_hint(k, out);
-        return true
-      } else if fallback(self).is_some() { return true }
-      if self.validate_hints { return false }
+        return Ok(true)
+      } else if fallback(self).is_some() { return Ok(true) }
+      if self.validate_hints { return Ok(false) }
     }
-    assert!(!self.all_hints, "step {} for {:?}: proof missing", self.step, ls);
-    if let Some(k) = self.propagate(ls) {
+    ensure!(!self.all_hints, malformed!("proof missing for {:?}", ls));
+    if let Some(k) = if xor { self.propagate_xor(ls)? } else { self.propagate(ls) } {
       self.finalize_hint(k, out);
-      return true
+      return Ok(true)
     }
-    false
+    Ok(false)
   }
 
   #[allow(clippy::too_many_arguments)]
   fn pr_resolve_one(&mut self,
     ls: &[i64], c: usize, witness_va: &MidVec<bool>, depth: usize,
     hint: Option<&[i64]>, out: &mut Hint, pre_rat: &mut Vec<i64>
-  ) {
+  ) -> Result<()> {
     let cl = &self.clauses[c];
-    if !self.full && !cl.marked { return }
+    if !self.full && !cl.marked { return Ok(()) }
     let step_start = out.steps.len();
     let mark_start = out.temp.len();
     #[allow(clippy::never_loop)]
     'done: loop {
-      assert!(!self.all_hints || hint.is_some(),
-        "step {} for {:?}: RAT resolvent with {:?} missing",
-        self.step, ls, cl);
+      ensure!(!self.all_hints || hint.is_some(),
+        malformed!("RAT resolvent of {:?} with {:?} missing", ls, cl));
       out.steps.push(-(cl.name as i64));
       if let Some(k) = self.va.unsat() {
         self.finalize_hint(k, out);
@@ -730,9 +1252,9 @@ impl Context {
           break 'done
         }
       }
-      assert!(self.build_step(&[], hint, out, |_| None),
-        "Step {}: Unit propagation stuck, cannot resolve clause {:?} with {:?}",
-        self.step, ls, self.clauses[c]);
+      ensure!(self.build_step(&[], hint, out, false, |_| None)?,
+        invalid!("unit propagation stuck, cannot resolve clause {:?} with {:?}",
+          ls, self.clauses[c]));
       break
     }
 
@@ -748,17 +1270,19 @@ impl Context {
         }
       } else { *lit = 0 }
     }
+    Ok(())
   }
 
   fn run_step<'a>(&mut self, ls: &[i64], pivot: Option<&i64>,
     in_wit: Option<&[i64]>, init: Option<&[i64]>,
     mut rats: Option<(&'a i64, &'a [i64])>,
     RatHint {hint: out, pre_rat, rat_set, witness, witness_va}: &mut RatHint
-  ) {
+  ) -> Result<()> {
     out.steps.clear();
     witness.clear();
     let success = if rats.is_none() {
-      self.build_step(ls, init, out, |this| {
+      let xor = self.xor_rup;
+      self.build_step(ls, init, out, xor, |this| {
         // Special case: A RAT step which introduces a fresh variable is indistinguishable
         // from a non-RAT step, because there are no negative numbers in the LRAT proof since no
         // clauses contain the negated pivot literal. In this case a correct and optimal hint
@@ -767,30 +1291,30 @@ impl Context {
         //
         // We assume that PR steps don't follow this path because any PR step with no touched
         // clauses can be expressed as a PR step with only one witness literal, which is a RAT step.
-        init?.is_empty().then(|| ())?;
+        init?.is_empty().then_some(())?;
         let pivot = *pivot?;
         if this.rat_set_lit == pivot {
-          rat_set.is_empty().then(|| ())?
+          rat_set.is_empty().then_some(())?
         } else if let Some(cbm) = &this.clauses_by_maxvar {
           let var = pivot.unsigned_abs() as usize - 1;
           if var < cbm.len() {
             for set in &cbm[var..] {
               if !set.is_empty() {
                 for &c in set {
-                  (!this.clauses[c].contains(&-pivot)).then(|| ())?
+                  (!this.clauses[c].contains(&-pivot)).then_some(())?
                 }
               }
             }
           }
         } else {
           for (_, cl) in &this.clauses {
-            (!cl.contains(&-pivot)).then(|| ())?
+            (!cl.contains(&-pivot)).then_some(())?
           }
         }
         witness.push(pivot);
         Some(())
-      })
-    } else if let Some(k) = self.propagate_hint(ls, init.unwrap_or(&[])) {
+      })?
+    } else if let Some(k) = self.propagate_hint(ls, init.unwrap_or(&[]))? {
       self.finalize_hint(k, out);
       true
     } else { false };
@@ -798,25 +1322,25 @@ impl Context {
     if success {
       self.clear_marks(out);
       self.va.clear_hyps();
-      return
+      return Ok(())
     }
 
     // A RAT step with no resolvents has no need for pre-RAT hint steps.
     // So if there are such steps then we assume it was just a failed RUP proof
-    assert!(!self.validate_hints || rats.is_some() || init.map_or(true, |init| init.is_empty()),
-      "step {}: Unit propagation stuck, failed to prove empty clause", self.step);
+    ensure!(!self.validate_hints || rats.is_some() || init.is_none_or(|init| init.is_empty()),
+      invalid!("unit propagation stuck, failed to prove empty clause"));
 
     if let Some(w) = in_wit {
       for &lit in w {
-        assert!(!self.va.is_false(lit) ||
+        ensure!(!self.va.is_false(lit) ||
             self.va.tru_stack.iter().rposition(|&l| l == -lit).unwrap() >= self.va.first_hyp,
-          "step {} failed, witness literal {} is complement of clause {:?}",
-          self.step, lit, self.clauses[self.va.reasons[-lit].clause().unwrap()]);
+          invalid!("witness literal {} is complement of clause {:?}",
+            lit, self.clauses[self.va.reasons[-lit].clause().unwrap()]));
         if !self.va.is_true(lit) { witness.push(lit) }
       }
     } else {
-      witness.push(*pivot.unwrap_or_else(||
-        panic!("step {}: Unit propagation stuck, failed to prove empty clause", self.step)))
+      witness.push(*pivot.ok_or_else(||
+        invalid!("unit propagation stuck, failed to prove empty clause"))?)
     }
 
     let depth = self.va.tru_stack.len();
@@ -867,10 +1391,10 @@ impl Context {
     while let Some((&s, rest)) = rats {
       let c = -s as u64;
       if self.lrat {
-        assert!(last.map_or(true, |l| l < c), "RAT steps must be sorted");
+        ensure!(last.is_none_or(|l| l < c), malformed!("RAT steps must be sorted"));
         last = Some(c);
       }
-      let c = self.get(c);
+      let c = self.get(c)?;
       let hint = if let Some(i) = rest.iter().position(|&i| i < 0) {
         let (chain, r) = rest.split_at(i);
         rats = r.split_first();
@@ -880,7 +1404,7 @@ impl Context {
         rest
       };
       if let Some(seen @ &mut false) = rat_set.get_mut(&c) {
-        self.pr_resolve_one(ls, c, witness_va, depth, Some(hint), out, pre_rat);
+        self.pr_resolve_one(ls, c, witness_va, depth, Some(hint), out, pre_rat)?;
         *seen = true;
         unseen -= 1;
       }
@@ -888,7 +1412,7 @@ impl Context {
 
     if unseen != 0 {
       for (&c, _) in rat_set.iter().filter(|(_, &seen)| !seen) {
-        self.pr_resolve_one(ls, c, witness_va, depth, None, out, pre_rat);
+        self.pr_resolve_one(ls, c, witness_va, depth, None, out, pre_rat)?;
       }
     }
 
@@ -901,7 +1425,8 @@ impl Context {
     out.steps.extend(pre_rat.drain(..).filter(|&l| l != 0));
     self.clear_marks(out);
     self.va.clear_hyps();
-    witness.iter().for_each(|&w| witness_va[w] = false)
+    witness.iter().for_each(|&w| witness_va[w] = false);
+    Ok(())
   }
 }
 
@@ -914,20 +1439,163 @@ fn as_add_step<'a>(lits: &'a mut [i64], witness: &'a [i64]) -> AddStepRef<'a> {
   else { AddStepRef::Two(lits, witness) }
 }
 
-fn elab<M: Mode>(
-  mode: M, full: bool, validate: bool, all_hints: bool, frat: File, w: &mut impl ModeWrite<Bin>
-) -> io::Result<()> {
+/// Options for elaboration, corresponding to the flags of `frat-xor elab`.
+#[derive(Clone, Debug, Default)]
+pub struct ElabOptions {
+  /// Elaborate every step, not just those needed for the empty clause (`--full`).
+  pub full: bool,
+  /// Reject invalid hints, and XORs and BNNs that are never deleted or finalized (`-s`).
+  pub validate: bool,
+  /// Reject invalid or missing hints (`-ss`, implies `validate`).
+  pub all_hints: bool,
+  /// Keep the temporary file in memory, with this initial capacity (`-m`),
+  /// or five times the size of the proof if it is 0.
+  pub in_memory: Option<usize>,
+  /// Check the output with the built-in XLRUP checker (`-v`).
+  pub verify: bool,
+  /// Copy comments into the output (`-c`).
+  pub comments: bool,
+  /// Write binary XLRUP (`-b`).
+  pub binary: bool,
+}
+
+/// Statistics from the backward pass of elaboration.
+#[derive(Clone, Debug, Default)]
+pub struct ElabStats {
+  /// Steps read from the FRAT-XOR proof.
+  pub steps: u64,
+  /// Original clauses used by the proof.
+  pub origs: u64,
+  /// Original XORs used by the proof.
+  pub orig_xors: u64,
+  /// Original BNNs used by the proof.
+  pub orig_bnns: u64,
+  /// Clause additions without a proof.
+  pub missing: u64,
+  /// XOR additions and clause-from-XOR steps without hints.
+  pub missing_xor: u64,
+}
+
+/// Statistics about the XLRUP proof written by the forward pass.
+#[derive(Clone, Debug, Default)]
+pub struct TrimStats {
+  /// Clauses added by RUP steps.
+  pub added: u64,
+  /// Clauses implied by XORs (`i cx`) or BNNs (`i cb`).
+  pub implied: u64,
+  /// XORs introduced by `o x`, `x` or `i x` lines.
+  pub xors: u64,
+  /// BNNs introduced by `o b` or `b` lines.
+  pub bnns: u64,
+  /// Clause deletions.
+  pub deleted: u64,
+  /// XOR deletions.
+  pub deleted_xors: u64,
+  /// BNN deletions.
+  pub deleted_bnns: u64,
+}
+
+/// The outcome of [`elaborate`].
+#[derive(Clone, Debug, Default)]
+pub struct ElabReport {
+  pub elab: ElabStats,
+  /// Statistics of the XLRUP proof, if a formula was given.
+  pub trim: Option<TrimStats>,
+  /// True if the XLRUP proof was checked by the built-in checker.
+  pub verified: bool,
+  /// Time spent in each phase.
+  pub timings: ElabTimings,
+  /// Size in bytes of the elaborated steps (the temporary file).
+  pub temp_size: u64,
+  /// Size in bytes of the XLRUP proof, if it was written to a file.
+  pub output_size: Option<u64>,
+}
+
+/// Time spent in each phase of [`elaborate`]; phases that did not run are `None`.
+#[derive(Clone, Debug, Default)]
+pub struct ElabTimings {
+  /// The backward pass, writing the elaborated steps.
+  pub elab: Duration,
+  /// Reading and parsing the formula.
+  pub parse_dimacs: Option<Duration>,
+  /// The forward pass, writing the XLRUP proof.
+  pub trim: Option<Duration>,
+  /// The built-in XLRUP checker.
+  pub verify: Option<Duration>,
+}
+
+impl ElabReport {
+  /// The report as a JSON object, as printed by `frat-xor elab --json`.
+  pub fn to_json(&self) -> String {
+    let e = &self.elab;
+    Obj::default()
+      .obj("elab", Obj::default()
+        .num("steps", e.steps).num("origs", e.origs).num("orig_xors", e.orig_xors)
+        .num("orig_bnns", e.orig_bnns).num("missing", e.missing).num("missing_xor", e.missing_xor))
+      .opt_obj("trim", self.trim.as_ref().map(|t| Obj::default()
+        .num("added", t.added).num("implied", t.implied).num("xors", t.xors).num("bnns", t.bnns)
+        .num("deleted", t.deleted).num("deleted_xors", t.deleted_xors).num("deleted_bnns", t.deleted_bnns)))
+      .bool("verified", self.verified)
+      .obj("timings", Obj::default()
+        .secs("elaborate", Some(self.timings.elab))
+        .secs("parse_dimacs", self.timings.parse_dimacs)
+        .secs("trim", self.timings.trim)
+        .secs("verify", self.timings.verify))
+      .num("temp_size", self.temp_size)
+      .opt_num("output_size", self.output_size)
+      .to_string()
+  }
+}
+
+/// Elaborate a FRAT-XOR proof backwards, writing the steps needed for the empty clause to `w`.
+pub fn elab<M: Mode>(
+  mode: M, opts: &ElabOptions, frat: File, w: &mut impl ModeWrite<Bin>
+) -> Result<ElabStats> {
+  elab_segments(opts, BackParser::new(mode, frat)?, w)
+}
+
+/// After `kind` step `id` did not find the XOR or BNN `missing` live, look further back for
+/// the step introducing it. If there is one, with no deletion in between, the XOR or BNN
+/// is live at the end of the proof without being finalized.
+fn never_finalized(steps: &mut StepIter<impl Segments>, (what, name): (&str, u64),
+  kind: &str, id: Option<u64>
+) -> Option<FratError> {
+  while let Some(Ok(s)) = steps.next() {
+    let (xor, i, intro) = match s {
+      Step::OrigXor(i, _) | Step::AddXor(i, ..) | Step::ImplyXor(i, ..) => (true, i, true),
+      Step::DelXor(i, _) | Step::FinalXor(i, _) => (true, i, false),
+      Step::OrigBnn(i, ..) | Step::AddBnn(i, ..) => (false, i, true),
+      Step::DelBnn(i, ..) | Step::FinalBnn(i, ..) => (false, i, false),
+      _ => continue
+    };
+    if i != name || xor != (what == "XOR") { continue }
+    if !intro { return None }
+    let (intro_kind, _) = s.kind();
+    let user = match id { Some(id) => format!("{} step {}", kind, id), None => format!("{} step", kind) };
+    return Some(invalid!("{} {} is never deleted or finalized, so {} cannot use it", what, name, user)
+      .at(intro_kind, Some(i)).offset(Some(steps.0.offset())))
+  }
+  None
+}
+
+/// Like [`elab`], but reads the proof from segments that come from the end of the proof.
+pub fn elab_segments(
+  opts: &ElabOptions, frat: impl Segments, w: &mut impl ModeWrite<Bin>
+) -> Result<ElabStats> {
+  let full = opts.full;
   let mut origs = Vec::new();
   let mut orig_xors = Vec::new();
   let mut orig_bnns = Vec::new();
   let ctx = &mut Context::default();
   ctx.full = full;
-  ctx.validate_hints = validate;
-  ctx.all_hints = all_hints;
+  ctx.validate_hints = opts.validate;
+  ctx.all_hints = opts.all_hints;
+  ctx.xor_rup = true;
   let hint = &mut RatHint::default();
   let mut last_non_finalize = None;
   let mut finalized_empty_clause = false;
-  for s in StepIter(BackParser::new(mode, frat)?) {
+  let mut steps = StepIter(frat);
+  let mut step = |s: Step, offset: usize| -> Result<()> {
     // eprintln!("<- {:?}", s);
     match s {
       Step::Comment(s) => ElabStep::Comment(s).write(w)?,
@@ -935,8 +1603,8 @@ fn elab<M: Mode>(
       Step::Orig(i, ls) => {
         ctx.step = i;
         last_non_finalize = Some(i);
-        let c = ctx.remove(i);
-        c.check_subsumed(&ls, ctx.step);
+        let c = ctx.remove(i)?;
+        c.check_subsumed(&ls)?;
         if full || c.marked {  // If the original clause is marked
           origs.push((i, c.lits)); // delay origs to the end
         }
@@ -945,14 +1613,14 @@ fn elab<M: Mode>(
 
       Step::Add(i, step, p) => {
         ctx.step = i;
-        let mut c = ctx.remove(i);
+        let mut c = ctx.remove(i)?;
         let kind = step.parse();
         let ls = kind.lemma();
-        c.check_subsumed(ls, ctx.step);
+        c.check_subsumed(ls)?;
         last_non_finalize = Some(i);
         if full || c.marked {
           let wit = kind.witness();
-          if let Some(Proof::LRAT(is)) = p {
+          let res = if let Some(Proof::LRAT(is)) = p {
             if let Some(start) = is.iter().position(|&i| i < 0).filter(|_| !ls.is_empty()) {
               let (init, rest) = is.split_at(start);
               ctx.run_step(&c, ls.first(), wit, Some(init), rest.split_first(), hint)
@@ -962,30 +1630,33 @@ fn elab<M: Mode>(
           } else {
             ctx.run_step(&c, ls.first(), wit, None, None, hint)
           };
+          if res.is_err() { ctx.remove_xor_reasons()? }
+          res?;
           let steps = &*hint.hint.steps;
           for &i in steps {
-            let i = i.unsigned_abs();
-            let c = ctx.get(i);
-            let cl = &mut ctx.clauses[c];
-            // let v = cs.get_mut(&i).unwrap();
-            if !cl.marked { // If the necessary clause is not active yet
-              cl.marked = true; // Make it active
-              if let [a, b, ..] = *cl.lits {
-                ctx.watch.del(false, a, c);
-                ctx.watch.del(false, b, c);
-                ctx.watch.add(true, a, c);
-                ctx.watch.add(true, b, c);
-              }
-              if !full { ElabStep::Del(i).write(w)? }
+            ctx.need_clause(i.unsigned_abs(), |i| if full { Ok(()) } else { ElabStep::Del(i).write(w) })?
+          }
+          // Clauses derived from XORs to make the step go through are implied
+          // right before it and deleted right after
+          let mut implied = vec![];
+          for (j, x) in mem::take(&mut ctx.xor_reasons) {
+            let cl = ctx.remove(j)?;
+            if steps.iter().any(|&i| i.unsigned_abs() == j) {
+              ElabStep::Del(j).write(w)?;
+              implied.push((j, cl.lits, x));
             }
           }
-          ElabStepRef::Add(i, as_add_step(&mut c.lits, &hint.witness), steps).write(w)?
+          ElabStepRef::Add(i, as_add_step(&mut c.lits, &hint.witness), steps).write(w)?;
+          for (j, ls, x) in implied {
+            ctx.mark_xors(&[x as i64], |x| if full { Ok(()) } else { ElabStep::DelXor(x).write(w) })?;
+            ElabStep::Imply(j, ls.into_vec(), vec![x as i64]).write(w)?
+          }
         }
         // else { eprintln!("delete {}", i); }
       }
 
       Step::Reloc(mut relocs) => {
-        ctx.reloc(&mut relocs);
+        ctx.reloc(&mut relocs)?;
         if !relocs.is_empty() { ElabStep::Reloc(relocs).write(w)? }
       }
 
@@ -993,246 +1664,305 @@ fn elab<M: Mode>(
         ctx.step = i;
         last_non_finalize = Some(i);
         dedup_vec(&mut ls);
-        ctx.insert(i, false, ls.into());
+        ctx.insert(i, false, ls.into())?;
         if full { ElabStep::Del(i).write(w)? }
       }
 
       Step::Final(i, mut ls) => {
         ctx.step = i;
         if let Some(j) = last_non_finalize {
-          panic!("final step {}: \
-            'f' steps should only appear at the end of the proof (step {} appears later).", i, j);
+          return Err(malformed!("'f' steps should only appear at the end of the proof (step {} appears later)", j))
         }
         // Identical to the Del case, except that the clause should be marked if empty
         dedup_vec(&mut ls);
         finalized_empty_clause |= ls.is_empty();
-        ctx.insert(i, ls.is_empty(), ls.into());
+        ctx.insert(i, ls.is_empty(), ls.into())?;
       }
 
       Step::Todo(_) => (),
     
       Step::OrigXor(i, ls) => {
-        orig_xors.push((i, ls.clone()));
+        let x = ctx.intro_xor(i, &ls)?;
+        if x.marked || full {
+          orig_xors.push((i, ls));
+        }
       }
 
       Step::AddXor(i, ls, p, u) => {
-        if let Some(Proof::LRAT(is)) = p {
-          if let Some(Proof::Unit(ref units)) = u {
-            for &i in units {
-              let c = ctx.get(i);
-              let cl = &mut ctx.clauses[c];
-              if !cl.marked { // If the necessary clause is not active yet
-                cl.marked = true; // Make it active
-                if let [a, b, ..] = *cl.lits {
-                  ctx.watch.del(false, a, c);
-                  ctx.watch.del(false, b, c);
-                  ctx.watch.add(true, a, c);
-                  ctx.watch.add(true, b, c);
-                }
-                if !full { ElabStep::Del(i).write(w)? }
-              }
-            }
+        ctx.step = i;
+        let x = ctx.intro_xor(i, &ls)?;
+        if !x.marked && !full {
+          // This XOR is never used, so neither are its hints
+        } else {
+          let is = match p {
+            Some(Proof::LRAT(is)) => is,
+            _ => ctx.find_xor_add_hint(&ls, match u {
+              Some(Proof::Unit(ref units)) => units,
+              _ => &[],
+            })?,
+          };
+          // The units given by the solver are replaced by the ones the sum needs
+          let units = ctx.xor_add_units(&ls, &is)?;
+          ctx.check_xor_add(&ls, &is, &units)?;
+          ctx.mark_xors(&is, |x| if full { Ok(()) } else { ElabStep::DelXor(x).write(w) })?;
+          for &i in &units {
+            ctx.need_clause(i, |i| if full { Ok(()) } else { ElabStep::Del(i).write(w) })?
           }
 
+          let u = if units.is_empty() { None } else { Some(Proof::Unit(units)) };
           ElabStep::AddXor(i, ls, is, u).write(w)?
-        } else {
-          panic!("add-xor step {}: add XOR step has no proof", i);
         }
       }
 
-      Step::DelXor(i, _ls) => {
-        ElabStep::DelXor(i).write(w)?
+      Step::DelXor(i, ls) => {
+        ctx.step = i;
+        ctx.undel_xor(i, &ls)?;
+        if full { ElabStep::DelXor(i).write(w)? }
       }
 
-      Step::Imply(i, ls, p) => {
+      Step::Imply(i, mut ls, p) => {
         ctx.step = i;
-        let c = ctx.remove(i);
-        c.check_subsumed(&ls, ctx.step);
+        let c = ctx.remove(i)?;
+        c.check_subsumed(&ls)?;
+        // cake_xlrup would count a repeated literal twice in the parity
+        dedup_vec(&mut ls);
 
-        if let Some(Proof::LRAT(is)) = p {
-          ElabStep::Imply(i, ls, is).write(w)?
+        if !full && !c.marked {
+          // The clause is never used, so neither are its hints
         } else {
-          panic!("imply step {}: imply step has no proof", i);
+          let is = match p {
+            Some(Proof::LRAT(is)) => is,
+            _ => ctx.find_xor_imply_hint(&ls)?,
+          };
+          ctx.check_xor_imply_later(i, offset, &ls, &is)?;
+          ctx.mark_xors(&is, |x| if full { Ok(()) } else { ElabStep::DelXor(x).write(w) })?;
+          ElabStep::Imply(i, ls, is).write(w)?
         }
       }
 
       Step::ImplyXor(i, ls, p) => {
-        if let Some(Proof::LRAT(is)) = p {
+        ctx.step = i;
+        let x = ctx.intro_xor(i, &ls)?;
+        if !x.marked && !full {
+          // This XOR is never used, so neither are its hints
+        } else if let Some(Proof::LRAT(is)) = p {
+          ctx.check_clauses_imply_xor(&ls, &is)?;
           for &i in &is {
-            let i = i.unsigned_abs();
-            let c = ctx.get(i);
-            let cl = &mut ctx.clauses[c];
-            if !cl.marked { // If the necessary clause is not active yet
-              cl.marked = true; // Make it active
-              if let [a, b, ..] = *cl.lits {
-                ctx.watch.del(false, a, c);
-                ctx.watch.del(false, b, c);
-                ctx.watch.add(true, a, c);
-                ctx.watch.add(true, b, c);
-              }
-              if !full { ElabStep::Del(i).write(w)? }
-            }
+            ctx.need_clause(i.unsigned_abs(), |i| if full { Ok(()) } else { ElabStep::Del(i).write(w) })?
           }
 
           ElabStep::ImplyXor(i, ls, is).write(w)?
         } else {
-          panic!("imply-xor step {}: imply XOR step has no proof", i);
+          return Err(malformed!("imply XOR step has no proof"))
         }
       } 
 
-      Step::FinalXor(i, _ls) => {
+      Step::FinalXor(i, ls) => {
+        ctx.step = i;
         if let Some(j) = last_non_finalize {
-          panic!("final-xor step {}: \
-            'f x' steps should only appear at the end of the proof (step {} appears later).", i, j);
+          return Err(malformed!("'f x' steps should only appear at the end of the proof (step {} appears later)", j))
         }
+        ctx.undel_xor(i, &ls)?;
       }
 
       Step::OrigBnn(i, ls, rhs, out) => {
-        orig_bnns.push((i, ls.clone(), rhs, out));
+        let b = ctx.intro_bnn(i, &ls, rhs, out)?;
+        if b.marked || full {
+          orig_bnns.push((i, ls, rhs, out));
+        }
       }
 
       Step::AddBnn(i, ls, rhs, out, p) => {
-        if let Some(Proof::LRAT(is)) = p {
-          for &i in (&is).iter().skip(1) {
-            let i = i.unsigned_abs();
-            let c = ctx.get(i);
-            let cl = &mut ctx.clauses[c];
-            if !cl.marked { // If the necessary clause is not active yet
-              cl.marked = true; // Make it active
-              if let [a, b, ..] = *cl.lits {
-                ctx.watch.del(false, a, c);
-                ctx.watch.del(false, b, c);
-                ctx.watch.add(true, a, c);
-                ctx.watch.add(true, b, c);
-              }
-              if !full { ElabStep::Del(i).write(w)? }
-            }
+        ctx.step = i;
+        let b = ctx.intro_bnn(i, &ls, rhs, out)?;
+        if !b.marked && !full {
+          // This BNN is never used, so neither are its hints
+        } else if let Some(Proof::LRAT(is)) = p {
+          if let Some(&b) = is.first() {
+            ctx.mark_bnn(b.unsigned_abs(), |b| if full { Ok(()) } else { ElabStep::DelBnn(b).write(w) })?;
+          }
+          for &i in is.iter().skip(1) {
+            ctx.need_clause(i.unsigned_abs(), |i| if full { Ok(()) } else { ElabStep::Del(i).write(w) })?
           }
 
           ElabStep::AddBnn(i, ls, rhs, out, is).write(w)?
         } else {
-          panic!("add-bnn step {}: add BNN step has no proof", i);
+          return Err(malformed!("add BNN step has no proof"))
         }
       } 
 
-      Step::DelBnn(i, _ls, _rhs, _out) => {
-        ElabStep::DelBnn(i).write(w)?
+      Step::DelBnn(i, ls, rhs, out) => {
+        ctx.step = i;
+        ctx.undel_bnn(i, Bnn::new(&ls, rhs, out))?;
+        if full { ElabStep::DelBnn(i).write(w)? }
       }
 
-      Step::BnnImply(i, ls, p, u) => {
+      Step::BnnImply(i, ls, p, _) => {
         ctx.step = i;
-        let c = ctx.remove(i);
-        c.check_subsumed(&ls, ctx.step);
-
-        if let Some(Proof::LRAT(is)) = p {
-          if let Some(Proof::Unit(ref units)) = u {
-            for &i in units {
-              let c = ctx.get(i);
-              let cl = &mut ctx.clauses[c];
-              if !cl.marked { // If the necessary clause is not active yet
-                cl.marked = true; // Make it active
-                if let [a, b, ..] = *cl.lits {
-                  ctx.watch.del(false, a, c);
-                  ctx.watch.del(false, b, c);
-                  ctx.watch.add(true, a, c);
-                  ctx.watch.add(true, b, c);
-                }
-                if !full { ElabStep::Del(i).write(w)? }
-              }
-            }
+        let c = ctx.remove(i)?;
+        c.check_subsumed(&ls)?;
+
+        if !full && !c.marked {
+          // The clause is never used, so neither are its hints
+        } else if let Some(Proof::LRAT(is)) = p {
+          // The units given by the solver are replaced by a minimal set
+          let units = match *is {
+            [b] => ctx.bnn_imply_units(&ls, b.unsigned_abs())?,
+            _ => vec![],
+          };
+          ctx.check_bnn_imply(&ls, &is, &units)?;
+          ctx.mark_bnn(is[0].unsigned_abs(), |b| if full { Ok(()) } else { ElabStep::DelBnn(b).write(w) })?;
+          for &i in &units {
+            ctx.need_clause(i, |i| if full { Ok(()) } else { ElabStep::Del(i).write(w) })?
           }
 
+          let u = if units.is_empty() { None } else { Some(Proof::Unit(units)) };
           ElabStep::BnnImply(i, ls, is, u).write(w)?
         } else {
-          panic!("bnn imply step {}: bnn imply step has no proof", i);
+          return Err(malformed!("bnn imply step has no proof"))
         }
       }
 
-      Step::FinalBnn(i, _ls, _rhs, _out) => {
+      Step::FinalBnn(i, ls, rhs, out) => {
+        ctx.step = i;
         if let Some(j) = last_non_finalize {
-          panic!("final-bnn step {}: \
-            'f b' steps should only appear at the end of the proof (step {} appears later).", i, j);
+          return Err(malformed!("'f b' steps should only appear at the end of the proof (step {} appears later)", j))
         }
+        ctx.undel_bnn(i, Bnn::new(&ls, rhs, out))?;
       }
     }
+    Ok(())
+  };
+  let mut stats = ElabStats::default();
+  while let Some(s) = steps.next() {
+    let s = s.map_err(|e| e.offset(Some(steps.0.offset())))?;
+    let (kind, id) = s.kind();
+    stats.steps += 1;
+    match s {
+      Step::Add(_, _, None) => stats.missing += 1,
+      Step::AddXor(_, _, None, _) | Step::Imply(_, _, None) => stats.missing_xor += 1,
+      _ => {}
+    }
+    let offset = steps.0.offset();
+    if let Err(e) = step(s, offset) {
+      let e = e.at(kind, id).offset(Some(offset));
+      return Err(ctx.missing.take().and_then(|m| never_finalized(&mut steps, m, kind, id)).unwrap_or(e))
+    }
+  }
+  if let Some((x, (_, pending))) = ctx.implicit_xors.iter().next() {
+    let p = &ctx.pending_implies[pending[0]];
+    return Err(invalid!("XOR {} to be accessed does not exist", x).at("imply", Some(p.i)).offset(Some(p.offset)))
   }
 
+  stats.origs = origs.len() as u64;
+  stats.orig_xors = orig_xors.len() as u64;
+  stats.orig_bnns = orig_bnns.len() as u64;
   for (i, ls) in origs { ElabStep::Orig(i, ls.into()).write(w)? }
   for (i, ls) in orig_xors { ElabStep::OrigXor(i, ls).write(w)? }
   for (i, ls, rhs, out) in orig_bnns { ElabStep::OrigBnn(i, ls, rhs, out).write(w)? }
 
-  assert!(finalized_empty_clause, "empty clause never finalized");
-  Ok(())
+  ensure!(finalized_empty_clause, malformed!("empty clause never finalized"));
+  Ok(stats)
 }
 
-struct DeleteLine<'a, W>(&'a mut W, u64, bool);
+struct DeleteLine<'a, W>(&'a mut W, u64, Vec<u64>);
 
-impl<'a, W: Write> DeleteLine<'a, W> {
-  fn with(lrat: &'a mut W, step: u64,
+impl<'a, W> DeleteLine<'a, W> {
+  fn with<M>(lrat: &'a mut W, step: u64,
     f: impl FnOnce(&mut DeleteLine<'a, W>) -> io::Result<()>
-  ) -> io::Result<()> {
-    let mut l = DeleteLine(lrat, step, false);
+  ) -> io::Result<()> where W: ModeWrite<M>, for<'b> XLRUPStepRef<'b>: Serialize<M> {
+    let mut l = DeleteLine(lrat, step, vec![]);
     f(&mut l)?;
-    if l.2 { writeln!(l.0, " 0")? }
+    if !l.2.is_empty() { XLRUPStepRef::Del(l.1, &l.2).write(l.0)? }
     Ok(())
   }
 
-  fn delete(&mut self, i: u64) -> io::Result<()> {
-    if mem::replace(&mut self.2, true) {
-      write!(self.0, " {}", i)
-    } else {
-      write!(self.0, "{} d {}", self.1, i)
+  fn delete(&mut self, i: u64) { self.2.push(i) }
+}
+
+/// The XOR and BNN constraints of the input formula, in input order.
+#[derive(Clone, Debug, Default)]
+pub struct Xnf {
+  pub xors: Vec<Vec<i64>>,
+  pub bnns: Vec<Bnn>,
+}
+
+/// Split an XNF formula into its clauses and the remaining constraints.
+pub fn split_xnf(dimacs: &str) -> Result<(Vec<Box<[i64]>>, Xnf)> {
+  let (mut cnf, mut xnf) = (vec![], Xnf::default());
+  for c in parse_xnf(dimacs.bytes())?.1 {
+    match c {
+      Constraint::Clause(mut c) => {dedup_vec(&mut c); cnf.push(c.into())}
+      Constraint::Xor(ls) => xnf.xors.push(ls),
+      Constraint::Bnn(bnn) => xnf.bnns.push(bnn),
     }
   }
+  Ok((cnf, xnf))
 }
 
-fn trim(
+/// Read the elaborated steps of `temp_it` forwards, writing the XLRUP proof to `lrat`.
+pub fn trim<M>(
   cnf: &[Box<[i64]>],
+  xnf: &Xnf,
   temp_it: impl Iterator<Item=Segment>,
   comments: bool,
-  lrat: &mut impl Write,
-) -> io::Result<()> {
+  lrat: &mut impl ModeWrite<M>,
+) -> Result<TrimStats> where for<'a> XLRUPStepRef<'a>: Serialize<M> {
+  let mut stats = TrimStats::default();
 
   let mut k = 0u64; // Counter for the last used ID
   let cnf: HashMap<PermClauseRef, u64> = // original CNF
     cnf.iter().map(|c| (PermClauseRef(c), {k += 1; k})).collect();
+  // Original XORs up to literal order and sign parity, and BNNs up to literal order.
+  // The input form is written out, since the checker compares them verbatim.
+  let xors: HashMap<Xor, &[i64]> = xnf.xors.iter().map(|ls| (Xor::from_lits(ls), &**ls)).collect();
+  let bnns: HashMap<(PermClauseRef, i64, i64), &[i64]> =
+    xnf.bnns.iter().map(|b| ((PermClauseRef(&b.lits), b.cutoff, b.out), &*b.lits)).collect();
   // Mapping between old and new IDs, where the bool is true if the old ID is a copy
   let mut map: HashMap<u64, u64> = HashMap::default();
   let mut copies: HashMap<u64, u32> = HashMap::default();
   let mut bp = ElabStepIter(temp_it).peekable();
   let mut used_origs = vec![0u8; k as usize];
   let mut rats = vec![];
-
-  while let Some(s) = bp.peek() {
+  // Mappings from the live XOR and BNN IDs to dense new IDs. XORs and BNNs that are not
+  // mapped were never written out, so their deletions are skipped.
+  let (mut xmap, mut next_x): (HashMap<u64, u64>, u64) = Default::default();
+  let (mut bmap, mut next_b): (HashMap<u64, u64>, u64) = Default::default();
+  let fresh = |map: &mut HashMap<u64, u64>, next: &mut u64, i| { *next += 1; map.insert(i, *next); *next };
+  let xid = |xmap: &HashMap<u64, u64>, i: u64, kind, id| xmap.get(&i).copied()
+    .ok_or_else(|| malformed!("XOR {} not found", i).at(kind, Some(id)));
+  let bid = |bmap: &HashMap<u64, u64>, i: u64, kind, id| bmap.get(&i).copied()
+    .ok_or_else(|| malformed!("BNN {} not found", i).at(kind, Some(id)));
+
+  while let Some(Ok(s)) = bp.peek() {
     if let ElabStep::Orig(_, _) = s {
-      if let Some(ElabStep::Orig(i, ls)) = bp.next() {
+      if let Some(Ok(ElabStep::Orig(i, ls))) = bp.next() {
         // eprintln!("-> Orig{:?}", (&i, &ls));
-        let j = *cnf.get(&PermClauseRef(&ls)).unwrap_or_else( // Find position of clause in original problem
-          || panic!("Orig step {} refers to nonexistent clause {:?}", i, ls));
+        let j = *cnf.get(&PermClauseRef(&ls)).ok_or_else( // Find position of clause in original problem
+          || invalid!("refers to nonexistent clause {:?}", ls).at("orig", Some(i)))?;
         let r = &mut used_origs[j as usize - 1];
         *r = r.saturating_add(1);
-        assert!(map.insert(i, j).is_none(), "Multiple orig steps with duplicate IDs");
+        ensure!(map.insert(i, j).is_none(), malformed!("multiple orig steps with ID {}", i));
         // eprintln!("{} -> {}", i, j);
         if ls.is_empty() {
-          writeln!(lrat, "{} 0 {} 0", k+1, j)?;
-          return Ok(())
+          XLRUPStepRef::Add(k+1, &[], &[j as i64]).write(lrat)?;
+          stats.added += 1;
+          return Ok(stats)
         }
       } else {unreachable!()}
     } else if let ElabStep::OrigXor(_, _) = s {
-      if let Some(ElabStep::OrigXor(i, ls)) = bp.next() {
-        write!(lrat, "o x {}", i)?;
-        for &x in &*ls { write!(lrat, " {}", x)? }
-        writeln!(lrat, " 0")?;
+      if let Some(Ok(ElabStep::OrigXor(i, ls))) = bp.next() {
+        let ls = *xors.get(&Xor::from_lits(&ls)).ok_or_else(
+          || invalid!("refers to nonexistent XOR {:?}", ls).at("orig-xor", Some(i)))?;
+        stats.xors += 1;
+        XLRUPStepRef::OrigXor(fresh(&mut xmap, &mut next_x, i), ls).write(lrat)?;
       } else {unreachable!()}
     } else if let ElabStep::OrigBnn(_, _, _, _) = s {
-      if let Some(ElabStep::OrigBnn(i, ls, rhs, out)) = bp.next() {
-        write!(lrat, "o b {}", i)?;
-        for &x in &*ls { write!(lrat, " {}", x)? }
-        if out == 0 {
-          writeln!(lrat, " 0 {} 0", rhs)?;
-        } else {
-          writeln!(lrat, " 0 {} {} 0", rhs, out)?;
-        }
+      if let Some(Ok(ElabStep::OrigBnn(i, ls, rhs, out))) = bp.next() {
+        let ls = *bnns.get(&(PermClauseRef(&ls), rhs, out)).ok_or_else(
+          || invalid!("refers to nonexistent BNN {:?} >= {} <-> {}", ls, rhs, out).at("orig-bnn", Some(i)))?;
+        stats.bnns += 1;
+        XLRUPStepRef::OrigBnn(fresh(&mut bmap, &mut next_b, i), ls, rhs, out).write(lrat)?;
       } else {unreachable!()} 
     } else {
       break;
@@ -1241,7 +1971,7 @@ fn trim(
 
   DeleteLine::with(lrat, k, |line| {
     for (j, &b) in used_origs.iter().enumerate() {
-      if b == 0 { line.delete(j as u64 + 1)? }
+      if b == 0 { stats.deleted += 1; line.delete(j as u64 + 1) }
     }
     Ok(())
   })?;
@@ -1249,11 +1979,11 @@ fn trim(
   while let Some(s) = bp.next() {
     // eprintln!("-> {:?}", s);
 
-    match s {
-      ElabStep::Comment(s) => if comments { writeln!(lrat, "{} c {}", k, s)? }
+    match s? {
+      ElabStep::Comment(s) => if comments { XLRUPStepRef::Comment(k, &s).write(lrat)? }
 
       ElabStep::Orig(i, _) =>
-        panic!("orig step {}: Orig steps must come at the beginning of the temp file", i),
+        return Err(malformed!("Orig steps must come at the beginning of the temp file").at("orig", Some(i))),
 
       ElabStep::Add(i, AddStep(ls), mut is) => {
         if let Some(cl) = match *is {
@@ -1261,26 +1991,22 @@ fn trim(
           _ => None,
         } {
           // A one-hint RUP step is a subsumed clause, so we can skip it
-          let cl = *map.get(&cl).unwrap_or_else(||
-            panic!("add step {}: proof step {:?} not found", i, cl));
+          let cl = *map.get(&cl).ok_or_else(|| malformed!("proof step {:?} not found", cl).at("add", Some(i)))?;
           map.insert(i, cl);
           // eprintln!("{} -> {} copy", i, cl);
           *copies.entry(cl).or_default() += 1;
         } else {
           k += 1; // Get the next fresh ID
           map.insert(i, k); // The ID of added clause is mapped to a fresh ID
+          stats.added += 1;
           // eprintln!("{} -> {}", i, k);
           let done = ls.is_empty();
 
-          write!(lrat, "{}", k)?;
-          for &x in &*ls { write!(lrat, " {}", x)? }
-          write!(lrat, " 0")?;
           let mut last_neg = None;
           let idx = i;
           for (i, x) in is.iter_mut().enumerate() {
             let ux = x.unsigned_abs();
-            let lit = *map.get(&ux).unwrap_or_else(||
-              panic!("add step {}: proof step {:?} not found", idx, ux)) as i64;
+            let lit = *map.get(&ux).ok_or_else(|| malformed!("proof step {:?} not found", ux).at("add", Some(idx)))? as i64;
             *x = if *x < 0 {
               if let Some((lit, j)) = last_neg { rats.push((lit, j, i)) }
               last_neg = Some((lit, i));
@@ -1292,16 +2018,15 @@ fn trim(
           if let Some((lit, j)) = last_neg { rats.push((lit, j, is.len())) }
           if let [(_, start, _), ..] = *rats {
             rats.sort_by_key(|p| p.0);
-            for &i in &is[..start] { write!(lrat, " {}", i)? }
+            let mut sorted = is[..start].to_vec();
             for (_, start, end) in rats.drain(..) {
-              for &i in &is[start..end] { write!(lrat, " {}", i)? }
+              sorted.extend_from_slice(&is[start..end]);
             }
-          } else {
-            for &i in &is { write!(lrat, " {}", i)? }
+            is = sorted;
           }
-          writeln!(lrat, " 0")?;
+          XLRUPStepRef::Add(k, &ls, &is).write(lrat)?;
 
-          if done {return Ok(())}
+          if done {return Ok(stats)}
         }
       }
 
@@ -1313,237 +2038,263 @@ fn trim(
         }
       }
 
-      ElabStep::Del(i) => DeleteLine::with(lrat, k, |line| {
-        let m = &mut map;
-        let used_origs = &mut used_origs;
-        let copies = &mut copies;
-        let mut delete = move |i| -> io::Result<()> {
-          let j = m.remove(&i).unwrap();
-          let last_copy = match copies.get_mut(&j) {
-            Some(val) if *val > 0 => { *val -= 1; false },
-            _ => true,
+      s @ (ElabStep::Del(_) | ElabStep::DelXor(_) | ElabStep::DelBnn(_)) => {
+        let (mut xs, mut bs) = (vec![], vec![]);
+        DeleteLine::with(lrat, k, |line| {
+          let m = &mut map;
+          let used_origs = &mut used_origs;
+          let copies = &mut copies;
+          let deleted = &mut stats.deleted;
+          let (xmap, bmap, xs, bs) = (&mut xmap, &mut bmap, &mut xs, &mut bs);
+          let mut delete = move |s| -> io::Result<()> {
+            match s {
+              ElabStep::Del(i) => {
+                let j = m.remove(&i).unwrap();
+                let last_copy = match copies.get_mut(&j) {
+                  Some(val) if *val > 0 => { *val -= 1; false },
+                  _ => true,
+                };
+                if last_copy && match used_origs.get_mut(j as usize - 1) {
+                  None => true,
+                  Some(&mut u8::MAX) => false,
+                  Some(refc) => { *refc -= 1; *refc == 0 }
+                } { *deleted += 1; line.delete(j) }
+              }
+              // XORs and BNNs that were never written out are skipped
+              ElabStep::DelXor(i) => if let Some(j) = xmap.remove(&i) { xs.push(j) },
+              ElabStep::DelBnn(i) => if let Some(j) = bmap.remove(&i) { bs.push(j) },
+              _ => unreachable!(),
+            }
+            Ok(())
           };
-          if last_copy && match used_origs.get_mut(j as usize - 1) {
-            None => true,
-            Some(&mut u8::MAX) => false,
-            Some(refc) => { *refc -= 1; *refc == 0 }
-          } { line.delete(j)? }
-          Ok(())
-        };
 
-        // Remove ID mapping to free space
-        delete(i)?;
-        // agglomerate additional del steps into this block
-        while let Some(&ElabStep::Del(i)) = bp.peek() {
-          bp.next();
-          delete(i)?;
-        }
-        Ok(())
-      })?,
+          // Remove ID mapping to free space
+          delete(s)?;
+          // agglomerate additional del steps into this block
+          while let Some(Ok(ElabStep::Del(_) | ElabStep::DelXor(_) | ElabStep::DelBnn(_))) = bp.peek() {
+            if let Some(Ok(s)) = bp.next() { delete(s)? }
+          }
+          Ok(())
+        })?;
+        stats.deleted_xors += xs.len() as u64;
+        stats.deleted_bnns += bs.len() as u64;
+        if !xs.is_empty() { XLRUPStepRef::DelXor(&xs).write(lrat)? }
+        if !bs.is_empty() { XLRUPStepRef::DelBnn(&bs).write(lrat)? }
+      }
 
       ElabStep::OrigXor(i, _) =>
-        panic!("orig-xor step {}: Orig XOR steps must come at the beginning of the temp file", i),
-
-      ElabStep::AddXor(i, ls, is, u) => {
-        write!(lrat, "x {}", i)?;
-        for &x in &*ls { write!(lrat, " {}", x)? }
-        write!(lrat, " 0")?;
-
-        for &x in &*is { write!(lrat, " {}", x)? }
+        return Err(malformed!("Orig XOR steps must come at the beginning of the temp file").at("orig-xor", Some(i))),
 
-        if let Some(Proof::Unit(mut units)) = u {
-          for ux in units.iter_mut() {
-            *ux = *map.get(&ux).unwrap_or_else(||
-              panic!("add-xor step {}: unit-proof step {:?} not found", i, ux)) as u64;
-          }
-          write!(lrat, " u")?;
-          for &x in &*units { write!(lrat, " {}", x)? }
+      ElabStep::AddXor(i, ls, mut is, u) => {
+        stats.xors += 1;
+        for x in is.iter_mut() {
+          *x = xid(&xmap, x.unsigned_abs(), "add-xor", i)? as i64;
         }
-
-        writeln!(lrat, " 0")?;
+        let mut units = match u { Some(Proof::Unit(units)) => units, _ => vec![] };
+        for ux in units.iter_mut() {
+          *ux = *map.get(ux).ok_or_else(|| malformed!("unit-proof step {:?} not found", ux).at("add-xor", Some(i)))?;
+        }
+        XLRUPStepRef::AddXor(fresh(&mut xmap, &mut next_x, i), &ls, &is, &units).write(lrat)?;
       }
 
-      ElabStep::DelXor(i) => writeln!(lrat, "x d {} 0", i)?,
-
-      ElabStep::Imply(i, ls, is) => {
+      ElabStep::Imply(i, ls, mut is) => {
+        for x in is.iter_mut() {
+          *x = xid(&xmap, x.unsigned_abs(), "imply", i)? as i64;
+        }
         k += 1;
         map.insert(i, k);
+        stats.implied += 1;
         let done = ls.is_empty();
-        write!(lrat, "i cx {}", k)?;
-        for &x in &*ls { write!(lrat, " {}", x)? }
-        write!(lrat, " 0")?;
-
-        for &x in &*is { write!(lrat, " {}", x)? }
-        writeln!(lrat, " 0")?;
+        XLRUPStepRef::Imply(k, &ls, &is).write(lrat)?;
 
-        if done {return Ok(())}
+        if done {return Ok(stats)}
       }
 
       ElabStep::ImplyXor(i, ls, mut is) => {
-        write!(lrat, "i x {}", i)?;
-        for &x in &*ls { write!(lrat, " {}", x)? }
-        write!(lrat, " 0")?;
-
+        stats.xors += 1;
         for x in is.iter_mut() {
           let ux = x.unsigned_abs();
-          *x = *map.get(&ux).unwrap_or_else(||
-            panic!("imply-xor step {}: clause-proof step {:?} not found", i, ux)) as i64;
+          *x = *map.get(&ux).ok_or_else(|| malformed!("clause-proof step {:?} not found", ux).at("imply-xor", Some(i)))? as i64;
         }
-        for &x in &*is { write!(lrat, " {}", x)? }
-        writeln!(lrat, " 0")?;
+        XLRUPStepRef::ImplyXor(fresh(&mut xmap, &mut next_x, i), &ls, &is).write(lrat)?;
       }
 
       ElabStep::OrigBnn(i, _, _, _) =>
-        panic!("orig-bnn step {}: Orig BNN steps must come at the beginning of the temp file", i),
+        return Err(malformed!("Orig BNN steps must come at the beginning of the temp file").at("orig-bnn", Some(i))),
 
       ElabStep::AddBnn(i, ls, rhs, out, mut is) => {
-        write!(lrat, "b {}", i)?;
-        for &x in &*ls { write!(lrat, " {}", x)? }
-        if out == 0 {
-          write!(lrat, " 0 {} 0", rhs)?;
-        } else {
-          write!(lrat, " 0 {} {} 0", rhs, out)?;
+        stats.bnns += 1;
+        if let Some(b) = is.first_mut() {
+          *b = bid(&bmap, b.unsigned_abs(), "add-bnn", i)? as i64;
         }
-
         for x in is.iter_mut().skip(1) {
           let ux = x.unsigned_abs();
-          *x = *map.get(&ux).unwrap_or_else(||
-            panic!("add-bnn step {}: clause-proof step {:?} not found", i, ux)) as i64;
+          *x = *map.get(&ux).ok_or_else(|| malformed!("clause-proof step {:?} not found", ux).at("add-bnn", Some(i)))? as i64;
         }
-        for &x in &*is { write!(lrat, " {}", x)? }
-        writeln!(lrat, " 0")?;
+        XLRUPStepRef::AddBnn(fresh(&mut bmap, &mut next_b, i), &ls, rhs, out, &is).write(lrat)?;
       }
 
-      ElabStep::DelBnn(i) => writeln!(lrat, "b d {} 0", i)?,
-
       ElabStep::BnnImply(i, ls, is, u) => {
         k += 1;
         map.insert(i, k);
+        stats.implied += 1;
         let done = ls.is_empty();
-        write!(lrat, "i cb {}", k)?;
-        for &x in &*ls { write!(lrat, " {}", x)? }
-        write!(lrat, " 0")?;
-
-        for &x in &*is { write!(lrat, " {}", x)? }
-
-        if let Some(Proof::Unit(mut units)) = u {
-          for ux in units.iter_mut() {
-            *ux = *map.get(&ux).unwrap_or_else(||
-              panic!("bnn-imply step {}: unit-proof step {:?} not found", i, ux)) as u64;
-          }
-          write!(lrat, " u")?;
-          for &x in &*units { write!(lrat, " {}", x)? }
+        let b = match *is {
+          [b] => bid(&bmap, b.unsigned_abs(), "bnn-imply", i)?,
+          _ => return Err(malformed!("expected a single BNN hint, got {:?}", is).at("bnn-imply", Some(i))),
+        };
+        let mut units = match u { Some(Proof::Unit(units)) => units, _ => vec![] };
+        for ux in units.iter_mut() {
+          *ux = *map.get(ux).ok_or_else(|| malformed!("unit-proof step {:?} not found", ux).at("bnn-imply", Some(i)))?;
         }
+        XLRUPStepRef::BnnImply(k, &ls, b, &units).write(lrat)?;
 
-        writeln!(lrat, " 0")?;
-
-        if done {return Ok(())}
+        if done {return Ok(stats)}
       }
     }
   }
 
-  panic!("did not find empty clause");
+  Err(malformed!("did not find empty clause"))
 }
 
-pub fn main(args: impl Iterator<Item=String>) -> io::Result<()> {
-  let mut args = args.peekable();
-  let frat_path = args.next().expect("missing proof file");
-
-  let full = matches!(args.peek(), Some(s) if s == "--full") && { args.next(); true };
-
-  let (validate, all_hints) = match args.peek().as_ref().map(|s| &***s) {
-    Some("-s") => { args.next(); (true, false) }
-    Some("-ss") => { args.next(); (true, true) }
-    _ => (false, false)
-  };
-
-  let mut frat = File::open(&frat_path)?;
-
-  let in_mem = match args.peek() {
-    Some(arg) if arg.starts_with("-m") => {
-      let n = if let Ok(n) = arg[2..].parse() { n }
-      else { frat.metadata()?.len().saturating_mul(5) };
-      args.next();
-      Some(n)
-    }
-    _ => None
-  };
-
-  let dimacs = args.next();
-  let (lrat_file, verify, comments) = match args.next() {
-    Some(ref s) if s == "-v" => (None, true, false),
-    Some(lrat_file) => {
-      let verify = matches!(args.peek(), Some(s) if s == "-v") && { args.next(); true };
-      let comments = matches!(args.peek(), Some(s) if s == "-c") && { args.next(); true };
-      (Some(lrat_file), verify, comments)
-    }
-    _ => (None, false, false),
-  };
-
-  if args.peek().is_some() {
-    eprintln!("\
-      Too many arguments to `frat-rs elab`. Expected:\n\n\
-      frat-rs elab FRATFILE [--full] [-s|-ss] [-m[NUM]] [DIMACSFILE [LRATFILE] [-v] [-c]]\n\n\
-      Note: options must appear in the specified order");
-    std::process::exit(2);
-  }
-
+/// Elaborate the FRAT-XOR proof at `frat_path`. If the formula `dimacs` is given, the XLRUP
+/// proof is written to `lrat` (if given) and checked if `opts.verify` is set. Unless
+/// `opts.in_memory` is set, the elaborated steps are kept in the file `{frat_path}.temp`.
+pub fn elaborate(
+  frat_path: impl AsRef<Path>, dimacs: Option<&Path>, lrat: Option<&Path>, opts: &ElabOptions
+) -> Result<ElabReport> {
+  let frat_path = frat_path.as_ref();
+  let mut frat = File::open(frat_path)?;
   let bin = detect_binary(&mut frat)?;
-  println!("elaborating...");
+  let in_mem = match opts.in_memory {
+    Some(0) => Some(frat.metadata()?.len().saturating_mul(5) as usize),
+    n => n,
+  };
+  let start = Instant::now();
   if let Some(temp_sz) = in_mem {
-    let mut temp = ModeWriter(Bin, Vec::with_capacity(temp_sz as usize));
-    if bin { elab(Bin, full, validate, all_hints, frat, &mut temp)? }
-    else { elab(Ascii, full, validate, all_hints, frat, &mut temp)? }
+    let mut temp = ModeWriter(Bin, Vec::with_capacity(temp_sz));
+    let elab = if bin { elab(Bin, opts, frat, &mut temp)? }
+    else { elab(Ascii, opts, frat, &mut temp)? };
 
-    return finish(dimacs, lrat_file, verify, comments, VecBackParser(temp.1))
+    let report = ElabReport { elab, temp_size: temp.1.len() as u64, ..Default::default() };
+    finish(report, start, dimacs, lrat, opts, VecBackParser(temp.1))
   } else {
-    let temp_path = format!("{}.temp", frat_path);
-    {
+    let mut temp_path = frat_path.as_os_str().to_owned();
+    temp_path.push(".temp");
+    let elab = {
       let mut temp_write = ModeWriter(Bin, BufWriter::new(File::create(&temp_path)?));
-      if bin { elab(Bin, full, validate, all_hints, frat, &mut temp_write)? }
-      else { elab(Ascii, full, validate, all_hints, frat, &mut temp_write)? };
+      let elab = if bin { elab(Bin, opts, frat, &mut temp_write)? }
+      else { elab(Ascii, opts, frat, &mut temp_write)? };
       temp_write.flush()?;
-    }
+      elab
+    };
 
-    let temp_read = BackParser::new(Bin, File::open(temp_path)?)?;
-    return finish(dimacs, lrat_file, verify, comments, temp_read)
+    let temp = File::open(temp_path)?;
+    let report = ElabReport { elab, temp_size: temp.metadata()?.len(), ..Default::default() };
+    finish(report, start, dimacs, lrat, opts, BackParser::new(Bin, temp)?)
   }
+}
 
-  fn finish(dimacs: Option<String>,
-    lrat_file: Option<String>, verify: bool, comments: bool,
-    temp_read: impl Iterator<Item=Segment>
-  ) -> io::Result<()> {
-    let dimacs = match dimacs {
-      Some(dimacs) => read_to_string(dimacs)?,
-      None => return Ok(())
-    };
-    println!("parsing DIMACS...");
-    let (_vars, cnf) = parse_dimacs_map(dimacs.bytes(), |mut c| {dedup_vec(&mut c); c.into()});
-    println!("trimming...");
-    if let Some(lrat_file) = lrat_file {
-      let mut lrat = BufWriter::new(File::create(&lrat_file)?);
-      trim(&cnf, temp_read, comments, &mut lrat)?;
-      lrat.flush()?;
-      if verify {
-        println!("verifying...");
-        let lrat = File::open(lrat_file)?;
-        check_lrat(Ascii, cnf, BufReader::new(lrat).bytes().map(Result::unwrap))?;
-        println!("VERIFIED");
-      }
-    } else if verify {
-      println!("verifying...");
-      let mut lrat = vec![];
-      trim(&cnf, temp_read, false, &mut lrat)?;
-      check_lrat(Ascii, cnf, lrat.into_iter())?;
-      println!("VERIFIED");
+/// Like [`elaborate`], for a binary FRAT-XOR proof held in memory.
+pub fn elaborate_bytes(
+  frat: Vec<u8>, dimacs: Option<&Path>, lrat: Option<&Path>, opts: &ElabOptions
+) -> Result<ElabReport> {
+  let start = Instant::now();
+  let mut temp = ModeWriter(Bin, Vec::with_capacity(opts.in_memory.unwrap_or(0).max(frat.len())));
+  let elab = elab_segments(opts, VecBackParser(frat), &mut temp)?;
+  let report = ElabReport { elab, temp_size: temp.1.len() as u64, ..Default::default() };
+  finish(report, start, dimacs, lrat, opts, VecBackParser(temp.1))
+}
+
+fn finish(mut report: ElabReport, start: Instant, dimacs: Option<&Path>, lrat_file: Option<&Path>,
+  opts: &ElabOptions, temp_read: impl Iterator<Item=Segment>
+) -> Result<ElabReport> {
+  // Time the phase since `start`, and restart the clock
+  let mut start = start;
+  let mut lap = || { let now = Instant::now(); now - mem::replace(&mut start, now) };
+  report.timings.elab = lap();
+  let dimacs = match dimacs {
+    Some(dimacs) => read_to_string(dimacs)?,
+    None => return Ok(report)
+  };
+  let (cnf, xnf) = split_xnf(&dimacs)?;
+  report.timings.parse_dimacs = Some(lap());
+  if let Some(lrat_file) = lrat_file {
+    let mut lrat = BufWriter::new(File::create(lrat_file)?);
+    report.trim = Some(if opts.binary {
+      trim(&cnf, &xnf, temp_read, opts.comments, &mut ModeWriter(Bin, &mut lrat))?
     } else {
-      trim(&cnf, temp_read, false, &mut io::sink())?;
+      trim(&cnf, &xnf, temp_read, opts.comments, &mut ModeWriter(Ascii, &mut lrat))?
+    });
+    lrat.flush()?;
+    report.output_size = Some(lrat.get_ref().metadata()?.len());
+    report.timings.trim = Some(lap());
+    if opts.verify {
+      let lrat = BufReader::new(File::open(lrat_file)?).bytes().map(io::Result::unwrap);
+      if opts.binary { check_xlrup(Bin, cnf, &xnf, lrat)? }
+      else { check_xlrup(Ascii, cnf, &xnf, lrat)? }
+      report.verified = true;
+      report.timings.verify = Some(lap());
     }
-    Ok(())
+  } else if opts.verify {
+    let mut lrat = ModeWriter(Bin, vec![]);
+    report.trim = Some(trim(&cnf, &xnf, temp_read, false, &mut lrat)?);
+    report.timings.trim = Some(lap());
+    check_xlrup(Bin, cnf, &xnf, lrat.1.into_iter())?;
+    report.verified = true;
+    report.timings.verify = Some(lap());
+  } else {
+    report.trim = Some(trim(&cnf, &xnf, temp_read, false, &mut ModeWriter(Bin, io::sink()))?);
+    report.timings.trim = Some(lap());
   }
+  Ok(report)
 }
 
-fn check_lrat(mode: impl Mode, cnf: Vec<Box<[i64]>>, lrat: impl Iterator<Item=u8>) -> io::Result<()> {
+const USAGE: &str = "\
+  frat-rs elab FRATFILE [DIMACSFILE [LRATFILE]] [--json] [--full] [-s|-ss] [-m[NUM]] [-b] [-v] [-c]\n\n\
+  Note: options may appear anywhere after `elab`\n\
+  Warning: -b writes binary XLRUP, which only `xlrupchk` and `xlrup-opt` read;\n\
+  the verified checker cake_xlrup needs ASCII output (leave out -b)";
+
+pub fn main(args: impl Iterator<Item=String>) -> Result<()> {
+  let (mut json, mut full, mut validate, mut all_hints) = (false, false, false, false);
+  let (mut in_memory, mut binary, mut verify, mut comments) = (None, false, false, false);
+  let mut files = vec![];
+  for arg in args {
+    match &*arg {
+      "--json" => json = true,
+      "--full" => full = true,
+      "-s" => validate = true,
+      "-ss" => (validate, all_hints) = (true, true),
+      "-b" => binary = true,
+      "-v" => verify = true,
+      "-c" => comments = true,
+      _ if arg.starts_with("-m") => in_memory = Some(arg[2..].parse().unwrap_or(0)),
+      _ => files.push(arg),
+    }
+  }
+  let mut files = files.into_iter();
+  let frat_path = files.next().unwrap_or_else(|| usage_error("Missing proof file for `frat-rs elab`", USAGE));
+  let dimacs = files.next();
+  let lrat_file = files.next();
+  let opts = ElabOptions { full, validate, all_hints, in_memory, verify, comments, binary };
+
+  if files.next().is_some() { usage_error("Too many arguments to `frat-rs elab`", USAGE) }
+
+  if !json { println!("elaborating...") }
+  let report = elaborate(frat_path, dimacs.as_deref().map(Path::new),
+    lrat_file.as_deref().map(Path::new), &opts)?;
+  if json { println!("{}", report.to_json()); return Ok(()) }
+  // Scripts look for these lines to see which phases ran
+  if report.timings.parse_dimacs.is_some() { println!("parsing DIMACS...") }
+  if report.trim.is_some() { println!("trimming...") }
+  if report.timings.verify.is_some() { println!("verifying...") }
+  if report.verified { println!("VERIFIED") }
+  Ok(())
+}
+
+/// Check an LRAT proof of the formula `cnf`.
+pub fn check_lrat(mode: impl Mode, cnf: Vec<Box<[i64]>>, lrat: impl Iterator<Item=u8>) -> Result<()> {
   let lp = LRATParser::from(mode, lrat);
   let mut k = 0;
   let ctx = &mut Context::default();
@@ -1557,60 +2308,183 @@ fn check_lrat(mode: impl Mode, cnf: Vec<Box<[i64]>>, lrat: impl Iterator<Item=u8
     k += 1;
     ctx.step = k;
     // eprintln!("{}: {:?}", k, c);
-    ctx.insert(k, true, c);
+    ctx.insert(k, true, c)?;
   }
 
   for (i, s) in lp {
     ctx.step = i;
     // eprintln!("{}: {:?}", i, s);
-    match s {
+    let kind = match s { LRATStep::Del(_) => "del", _ => "add" };
+    let step = || -> Result<bool> { match s {
       LRATStep::Comment(_) => {}
 
       LRATStep::Add(add, p) => {
-        assert!(i > k, "out-of-order LRAT proofs not supported");
+        ensure!(i > k, malformed!("out-of-order LRAT proofs not supported"));
         k = i;
-        let add = add.parse_into(|kind| {
+        let (res, add) = add.parse_into(|kind| {
           let ls = kind.lemma();
           let wit = kind.witness();
           ctx.reserve(ls);
           // eprintln!("{}: {:?} {:?}", k, ls, p);
           if let Some(start) = p.iter().position(|&i| i < 0).filter(|_| !ls.is_empty()) {
             let (init, rest) = p.split_at(start);
-            ctx.run_step(ls, ls.first(), wit, Some(init), rest.split_first(), hint);
+            ctx.run_step(ls, ls.first(), wit, Some(init), rest.split_first(), hint)
           } else {
-            ctx.run_step(ls, ls.first(), wit, Some(&p), None, hint);
+            ctx.run_step(ls, ls.first(), wit, Some(&p), None, hint)
           }
-        }).1;
-        if add.is_empty() { return Ok(()) }
-        ctx.insert_no_reserve(i, true, add.into());
+        });
+        res?;
+        if add.is_empty() { return Ok(true) }
+        ctx.insert_no_reserve(i, true, add.into())?;
       }
 
       LRATStep::Del(ls) => {
-        assert!(i >= k, "out-of-order LRAT proofs not supported");
+        ensure!(i >= k, malformed!("out-of-order LRAT proofs not supported"));
         k = i;
-        for c in ls { ctx.remove(c.try_into().unwrap()); }
+        for c in ls { ctx.remove(c.try_into().unwrap())?; }
       }
     }
+    Ok(false) };
+    if step().map_err(|e| e.at(kind, Some(i)))? { return Ok(()) }
   }
 
-  panic!("did not find empty clause")
+  Err(invalid!("did not find empty clause"))
 }
 
-pub fn lratchk(mut args: impl Iterator<Item=String>) -> io::Result<()> {
-  let dimacs = args.next().expect("missing input file");
-  let (_vars, cnf) = parse_dimacs(read_to_string(dimacs)?.bytes());
-  let lrat = File::open(args.next().expect("missing proof file"))?;
-  check_lrat(Ascii, cnf, BufReader::new(lrat).bytes().map(Result::unwrap))
+/// Check an XLRUP proof of the formula `cnf` with the constraints `xnf`.
+pub fn check_xlrup<M, I>(mode: M, cnf: Vec<Box<[i64]>>, xnf: &Xnf, lrat: I) -> Result<()>
+where XLRUPParser<M, I>: Iterator<Item=Result<XLRUPStep>> {
+  let mut k = 0;
+  let ctx = &mut Context::default();
+  ctx.validate_hints = true;
+  ctx.all_hints = true;
+  ctx.lrat = true;
+  ctx.full = true;
+  let hint = &mut RatHint::default();
+  // Like cake_xlrup, original XORs and BNNs must be given exactly as in the input formula
+  let xors: HashSet<&[i64]> = xnf.xors.iter().map(|ls| &**ls).collect();
+  let bnns: HashSet<(&[i64], i64, i64)> = xnf.bnns.iter().map(|b| (&*b.lits, b.cutoff, b.out)).collect();
+
+  for c in cnf {
+    k += 1;
+    ctx.step = k;
+    ctx.insert(k, true, c)?;
+  }
+
+  for s in XLRUPParser::from(mode, lrat) {
+    let s = s?;
+    let (kind, id) = s.kind();
+    let step = || -> Result<bool> { match s {
+      XLRUPStep::Comment(_, _) => {}
+
+      XLRUPStep::Add(i, ls, p) => {
+        ensure!(i > k, malformed!("out-of-order XLRUP proofs not supported"));
+        k = i;
+        ctx.step = i;
+        ctx.reserve(&ls);
+        ctx.run_step(&ls, ls.first(), None, Some(&p), None, hint)?;
+        if ls.is_empty() { return Ok(true) }
+        ctx.insert_no_reserve(i, true, ls.into())?;
+      }
+
+      XLRUPStep::Del(_, ls) => for c in ls { ctx.remove(c)?; }
+
+      XLRUPStep::OrigXor(i, ls) => {
+        ctx.step = i;
+        ensure!(xors.contains(&*ls),
+          invalid!("XOR {:?} is not in the input formula with its literals in this order", ls));
+        ctx.insert_xor(i, true, &ls)?;
+      }
+
+      XLRUPStep::AddXor(i, ls, is, u) => {
+        ctx.step = i;
+        ctx.check_xor_add(&ls, &is, &u)?;
+        ctx.insert_xor(i, true, &ls)?;
+      }
+
+      XLRUPStep::DelXor(is) => for i in is {
+        ctx.remove_xor(i)?;
+      }
+
+      XLRUPStep::Imply(i, ls, is) => {
+        ensure!(i > k, malformed!("out-of-order XLRUP proofs not supported"));
+        k = i;
+        ctx.step = i;
+        ctx.check_xor_imply(&ls, &is)?;
+        if ls.is_empty() { return Ok(true) }
+        ctx.insert(i, true, ls.into())?;
+      }
+
+      XLRUPStep::ImplyXor(i, ls, is) => {
+        ctx.step = i;
+        ctx.check_clauses_imply_xor(&ls, &is)?;
+        ctx.insert_xor(i, true, &ls)?;
+      }
+
+      XLRUPStep::OrigBnn(i, ls, rhs, out) => {
+        ctx.step = i;
+        ensure!(bnns.contains(&(&*ls, rhs, out)),
+          invalid!("BNN {:?} >= {} <-> {} is not in the input formula with its literals in this order", ls, rhs, out));
+        ctx.insert_bnn(i, true, Bnn::new(&ls, rhs, out))?;
+      }
+
+      XLRUPStep::AddBnn(..) => return Err(malformed!("BNN addition steps are not supported")),
+
+      XLRUPStep::DelBnn(is) => for i in is {
+        ctx.remove_bnn(i)?;
+      }
+
+      XLRUPStep::BnnImply(i, ls, b, u) => {
+        ensure!(i > k, malformed!("out-of-order XLRUP proofs not supported"));
+        k = i;
+        ctx.step = i;
+        ctx.check_bnn_imply(&ls, &[b as i64], &u)?;
+        if ls.is_empty() { return Ok(true) }
+        ctx.insert(i, true, ls.into())?;
+      }
+    }
+    Ok(false) };
+    if step().map_err(|e| e.at(kind, id))? { return Ok(()) }
+  }
+
+  Err(invalid!("did not find empty clause"))
 }
 
-fn refrat_pass(elab: File, w: &mut impl ModeWrite) -> io::Result<()> {
+pub fn xlrupchk(mut args: impl Iterator<Item=String>) -> Result<()> {
+  const USAGE: &str = "frat-xor xlrupchk DIMACSFILE XLRUPFILE";
+  let dimacs = args.next().unwrap_or_else(|| usage_error("Missing input file for `frat-xor xlrupchk`", USAGE));
+  let proof = args.next().unwrap_or_else(|| usage_error("Missing proof file for `frat-xor xlrupchk`", USAGE));
+  let (cnf, xnf) = split_xnf(&read_to_string(dimacs)?)?;
+  let mut lrat = File::open(proof)?;
+  let bin = detect_binary(&mut lrat)?;
+  lrat.seek(SeekFrom::Start(0))?;
+  let lrat = BufReader::new(lrat).bytes().map(io::Result::unwrap);
+  if bin { check_xlrup(Bin, cnf, &xnf, lrat)? }
+  else { check_xlrup(Ascii, cnf, &xnf, lrat)? }
+  println!("s VERIFIED UNSAT");
+  Ok(())
+}
+
+pub fn lratchk(mut args: impl Iterator<Item=String>) -> Result<()> {
+  const USAGE: &str = "frat-xor lratchk DIMACSFILE LRATFILE";
+  let dimacs = args.next().unwrap_or_else(|| usage_error("Missing input file for `frat-xor lratchk`", USAGE));
+  let proof = args.next().unwrap_or_else(|| usage_error("Missing proof file for `frat-xor lratchk`", USAGE));
+  let (_vars, cnf) = parse_dimacs(read_to_string(dimacs)?.bytes())?;
+  let lrat = File::open(proof)?;
+  check_lrat(Ascii, cnf, BufReader::new(lrat).bytes().map(io::Result::unwrap))
+}
+
+fn refrat_pass(elab: File, w: &mut impl ModeWrite) -> Result<()> {
 
   let mut ctx: HashMap<u64, Vec<i64>> = HashMap::default();
   let mut ctx_xor: HashMap<u64, Vec<i64>> = HashMap::default();
   let mut ctx_bnn: HashMap<u64, (Vec<i64>, i64, i64)> = HashMap::default();
-  for s in ElabStepIter(BackParser::new(Bin, elab)?) {
+  let mut steps = ElabStepIter(BackParser::new(Bin, elab)?);
+  while let Some(s) = steps.next() {
     // eprintln!("-> {:?}", s);
 
+    let s = s.map_err(|e| e.offset(Some(steps.0.offset())))?;
+    let (kind, id) = s.kind();
     match s {
       ElabStep::Comment(s) => ElabStep::Comment(s).write(w)?,
 
@@ -1634,7 +2508,8 @@ fn refrat_pass(elab: File, w: &mut impl ModeWrite) -> io::Result<()> {
       }
 
       ElabStep::Del(i) => {
-        Step::Del(i, ctx.remove(&i).unwrap()).write(w)?;
+        let ls = ctx.remove(&i).ok_or_else(|| malformed!("clause {} to be deleted does not exist", i).at(kind, id))?;
+        Step::Del(i, ls).write(w)?;
       }
 
       ElabStep::OrigXor(i, ls) => {
@@ -1648,7 +2523,8 @@ fn refrat_pass(elab: File, w: &mut impl ModeWrite) -> io::Result<()> {
       }
 
       ElabStep::DelXor(i) => {
-        Step::DelXor(i, ctx_xor.remove(&i).unwrap()).write(w)?;
+        let ls = ctx_xor.remove(&i).ok_or_else(|| malformed!("XOR {} to be deleted does not exist", i).at(kind, id))?;
+        Step::DelXor(i, ls).write(w)?;
       }
 
       ElabStep::Imply(i, ls, is) => {
@@ -1672,7 +2548,7 @@ fn refrat_pass(elab: File, w: &mut impl ModeWrite) -> io::Result<()> {
       }
 
       ElabStep::DelBnn(i) => {
-        let (ls, rhs, out) = ctx_bnn.remove(&i).unwrap();
+        let (ls, rhs, out) = ctx_bnn.remove(&i).ok_or_else(|| malformed!("BNN {} to be deleted does not exist", i).at(kind, id))?;
         Step::DelBnn(i, ls, rhs, out).write(w)?;
       }
 
@@ -1684,16 +2560,212 @@ fn refrat_pass(elab: File, w: &mut impl ModeWrite) -> io::Result<()> {
   }
 
   for (i, s) in ctx { Step::Final(i, s).write(w)? }
-  // todo: add XOR to final
-  // todo: add BNN to final
+  for (i, ls) in ctx_xor { Step::FinalXor(i, ls).write(w)? }
+  for (i, (ls, rhs, out)) in ctx_bnn { Step::FinalBnn(i, ls, rhs, out).write(w)? }
 
   Ok(())
 }
 
-pub fn refrat(mut args: impl Iterator<Item=String>) -> io::Result<()> {
-  let elab_path = args.next().expect("missing elab file");
-  let frat_path = args.next().expect("missing frat file");
+pub fn refrat(mut args: impl Iterator<Item=String>) -> Result<()> {
+  const USAGE: &str = "frat-xor refrat ELABFILE FRATFILE";
+  let elab_path = args.next().unwrap_or_else(|| usage_error("Missing elab file for `frat-xor refrat`", USAGE));
+  let frat_path = args.next().unwrap_or_else(|| usage_error("Missing output file for `frat-xor refrat`", USAGE));
   let w = &mut ModeWriter(DefaultMode, BufWriter::new(File::create(&frat_path)?));
   refrat_pass(File::open(elab_path)?, w)?;
-  w.flush()
+  Ok(w.flush()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::ErrorKind;
+
+  /// Elaborate the ASCII FRAT-XOR proof `frat`, with `-s` if `validate` is set.
+  fn elab_str(frat: &str, validate: bool) -> Result<ElabStats> {
+    let path = std::env::temp_dir().join(format!("frat-xor-{}-{:?}.xfrat",
+      std::process::id(), std::thread::current().id()));
+    std::fs::write(&path, frat)?;
+    let opts = ElabOptions { validate, ..Default::default() };
+    let res = elab(Ascii, &opts, File::open(&path)?, &mut ModeWriter(Bin, io::sink()));
+    std::fs::remove_file(&path)?;
+    res
+  }
+
+  // example/test_1 with the original XOR 1 copied into XOR 2, which proves the clause -1
+  const STEPS: &str = "\
+    o 1 1 0\n\
+    o x 1 -1 0\n\
+    a x 2 -1 0 l 1 0\n\
+    i 2 -1 0 l 2 0\n\
+    a 3 0 l 1 2 0\n";
+  const FINALS: &str = "f 1 1 0\nf 2 -1 0\nf 3 0\nf x 2 -1 0\n";
+
+  fn assert_err(res: Result<ElabStats>, step: &str, id: u64, cause: &str) {
+    let e = res.unwrap_err();
+    assert_eq!((e.kind, e.step, e.id), (ErrorKind::Invalid, Some(step), Some(id)), "{}", e);
+    assert_eq!(e.cause, cause);
+  }
+
+  #[test] fn finals() {
+    let frat = format!("{}{}f x 1 -1 0\n", STEPS, FINALS);
+    elab_str(&frat, false).unwrap();
+    elab_str(&frat, true).unwrap();
+  }
+
+  #[test] fn missing_final() {
+    let frat = format!("{}{}", STEPS, FINALS);
+    assert_err(elab_str(&frat, false), "orig-xor", 1,
+      "XOR 1 is never deleted or finalized, so add-xor step 2 cannot use it");
+    // XOR 2 stays live until the end of the proof, unless finals are required
+    let frat = format!("{}f 1 1 0\nf 2 -1 0\nf 3 0\nf x 1 -1 0\n", STEPS);
+    elab_str(&frat, false).unwrap();
+    assert_err(elab_str(&frat, true), "add-xor", 2, "XOR 2 is never deleted or finalized");
+  }
+
+  #[test] fn double_final() {
+    let frat = format!("{}{}f x 1 -1 0\nf x 1 -1 0\n", STEPS, FINALS);
+    assert_err(elab_str(&frat, false), "final-xor", 1,
+      "XOR 1 is deleted or finalized again later in the proof");
+  }
+
+  #[test] fn final_after_deletion() {
+    let frat = format!("{}d x 1 -1 0\n{}f x 1 -1 0\n", STEPS, FINALS);
+    assert_err(elab_str(&frat, false), "del-xor", 1,
+      "XOR 1 is deleted or finalized again later in the proof");
+  }
+
+  #[test] fn mismatched_deletion() {
+    let frat = format!("{}a x 3 -1 0 l 1 0\nd x 3 -1 2 0\n{}f x 1 -1 0\n", STEPS, FINALS);
+    let e = elab_str(&frat, false).unwrap_err();
+    assert_eq!((e.step, e.id), (Some("add-xor"), Some(3)), "{}", e);
+    assert!(e.cause.contains("will later be deleted as"), "{}", e);
+  }
+
+  /// A context with the clauses `cnf`, numbered from 1.
+  fn with_clauses(cnf: &[&[i64]]) -> Context {
+    let mut ctx = Context::default();
+    for (i, &c) in cnf.iter().enumerate() { ctx.insert(i as u64 + 1, true, c.into()).unwrap() }
+    ctx
+  }
+
+  // Each clause rules out one assignment violating x1 + x2 + x3 = 1
+  const XOR_CLAUSES: [&[i64]; 4] = [&[1, 2, 3], &[-1, -2, 3], &[-1, 2, -3], &[1, -2, -3]];
+
+  #[test] fn clauses_imply_xor() {
+    with_clauses(&XOR_CLAUSES).check_clauses_imply_xor(&[1, 2, 3], &[1, 2, 3, 4]).unwrap();
+  }
+
+  #[test] fn subsuming_clause_implies_xor() {
+    // 1 2 rules out 1 2 3 along with an assignment satisfying the XOR
+    let ctx = with_clauses(&[&[1, 2], XOR_CLAUSES[1], XOR_CLAUSES[2], XOR_CLAUSES[3]]);
+    ctx.check_clauses_imply_xor(&[1, 2, 3], &[1, 2, 3, 4]).unwrap();
+  }
+
+  #[test] fn missing_clause() {
+    let ctx = with_clauses(&XOR_CLAUSES[..3]);
+    let e = ctx.check_clauses_imply_xor(&[1, 2, 3], &[1, 2, 3]).unwrap_err();
+    assert!(e.cause.starts_with("clauses [1, 2, 3] do not rule out 1 assignments"), "{}", e);
+    assert!(e.cause.ends_with("including [[-1, 2, 3]]"), "{}", e);
+  }
+
+  #[test] fn long_clausal_xor() {
+    let ls: Vec<i64> = (1..=MAX_CLAUSAL_XOR as i64 + 1).collect();
+    let e = with_clauses(&[]).check_clauses_imply_xor(&ls, &[]).unwrap_err();
+    assert!(e.cause.ends_with("has more than 20 variables, too many to check against clauses"), "{}", e);
+  }
+
+  /// A context with the unit clause 2 and the XORs 1 + 2 = 1 and 2 + 3 = 0 as XORs 1 and 2.
+  fn with_xors() -> Context {
+    let mut ctx = with_clauses(&[&[2]]);
+    ctx.insert_xor(1, true, &[1, 2]).unwrap();
+    ctx.insert_xor(2, true, &[-2, 3]).unwrap();
+    ctx
+  }
+
+  #[test] fn xor_add() {
+    let ctx = with_xors();
+    ctx.check_xor_add(&[3, 1], &[1, 2], &[]).unwrap();
+    // Setting 2 in XOR 1 leaves 1 = 0
+    ctx.check_xor_add(&[-1], &[1], &[1]).unwrap();
+  }
+
+  #[test] fn xor_add_diagnostics() {
+    let ctx = with_xors();
+    let e = ctx.check_xor_add(&[1, 2, 3], &[1, 2], &[]).unwrap_err();
+    assert_eq!(e.cause, "XOR [1, 2, 3] = 1 differs from the sum [1, 3] = 1 of XORs [1, 2] on variables [2]");
+    let e = ctx.check_xor_add(&[-1, 3], &[1, 2], &[]).unwrap_err();
+    assert_eq!(e.cause, "XOR [1, 3] = 0 differs in parity from the sum [1, 3] = 1 of XORs [1, 2]");
+    let e = ctx.check_xor_add(&[1], &[1], &[]).unwrap_err();
+    assert_eq!(e.cause, "XOR [1] = 1 differs from the sum [1, 2] = 1 of XORs [1] on variables [2]");
+  }
+
+  #[test] fn xor_imply() {
+    let ctx = with_xors();
+    ctx.check_xor_imply(&[1, 2], &[1]).unwrap();
+    ctx.check_xor_imply(&[-1, -2, -2], &[1]).unwrap();
+    ctx.check_xor_imply(&[1, 3], &[1, 2]).unwrap();
+    let e = ctx.check_xor_imply(&[1, -2], &[1]).unwrap_err();
+    assert_eq!(e.cause, "the assignment [2, -1] falsifying clause [-2, 1] satisfies the sum [1, 2] = 1 of XORs [1]");
+    let e = ctx.check_xor_imply(&[1], &[1]).unwrap_err();
+    assert_eq!(e.cause, "clause [1] does not mention variables [2] of the sum [1, 2] = 1 of XORs [1]");
+  }
+
+  #[test] fn imply_from_unfinalized_xor() {
+    // example/test_1 without `f x 1 -1 0`: the clause-from-XOR step waits for `o x 1`
+    elab_str("o 1 1 0\no x 1 -1 0\ni 2 -1 0 l 1 0\na 3 0 l 1 2 0\nf 1 1 0\nf 2 -1 0\nf 3 0\n", false).unwrap();
+    let e = elab_str("o 1 1 0\no x 1 -1 0\ni 2 -1 0 l 4 0\na 3 0 l 1 2 0\nf 1 1 0\nf 2 -1 0\nf 3 0\n", false)
+      .unwrap_err();
+    assert_eq!((e.step, e.id), (Some("imply"), Some(2)), "{}", e);
+    assert_eq!(e.cause, "XOR 4 to be accessed does not exist");
+  }
+
+  /// Trim the original steps `origs` of the formula `dimacs`, followed by an empty clause.
+  fn trim_origs(dimacs: &str, origs: Vec<ElabStep>) -> Result<String> {
+    let (cnf, xnf) = split_xnf(dimacs)?;
+    // The elaborated steps are read from the end
+    let mut temp = ModeWriter(Bin, vec![]);
+    ElabStep::Imply(9, vec![], vec![]).write(&mut temp)?;
+    for s in origs.into_iter().rev() { s.write(&mut temp)? }
+    let mut xlrup = ModeWriter(Ascii, vec![]);
+    trim(&cnf, &xnf, VecBackParser(temp.1), false, &mut xlrup)?;
+    Ok(String::from_utf8(xlrup.1).unwrap())
+  }
+
+  const XNF: &str = "p cnf 4 3\n1 -2 0\nx 1 2 0\nb 1 2 3 0 2 4 0\n";
+
+  #[test] fn trim_origs_as_in_input() {
+    // The input forms are written, whatever the order of literals and signs in the proof
+    let out = trim_origs(XNF, vec![
+      ElabStep::Orig(7, vec![-2, 1]),
+      ElabStep::OrigXor(5, vec![-2, -1]),
+      ElabStep::OrigBnn(6, vec![3, 1, 2], 2, 4),
+    ]).unwrap();
+    assert!(out.starts_with("o x 1 1 2 0\no b 1 1 2 3 0 2 4 0\n"), "{}", out);
+  }
+
+  #[test] fn trim_invented_origs() {
+    let e = trim_origs(XNF, vec![ElabStep::OrigXor(5, vec![-1, 2])]).unwrap_err();
+    assert_eq!((e.step, e.id), (Some("orig-xor"), Some(5)), "{}", e);
+    assert_eq!(e.cause, "refers to nonexistent XOR [-1, 2]");
+    let e = trim_origs(XNF, vec![ElabStep::OrigBnn(6, vec![1, 2, 3], 2, -4)]).unwrap_err();
+    assert_eq!((e.step, e.id), (Some("orig-bnn"), Some(6)), "{}", e);
+    assert_eq!(e.cause, "refers to nonexistent BNN [1, 2, 3] >= 2 <-> -4");
+    let e = trim_origs(XNF, vec![ElabStep::Orig(7, vec![1, 2])]).unwrap_err();
+    assert_eq!((e.step, e.id), (Some("orig"), Some(7)), "{}", e);
+  }
+
+  #[test] fn examples() {
+    for n in 1..=6 {
+      let path = format!("{}/example/test_{}", env!("CARGO_MANIFEST_DIR"), n);
+      let run = || -> Result<()> {
+        let mut temp = ModeWriter(Bin, vec![]);
+        elab(Ascii, &ElabOptions::default(), File::open(format!("{}.xfrat", path))?, &mut temp)?;
+        let (cnf, xnf) = split_xnf(&read_to_string(format!("{}.xnf", path))?)?;
+        let mut xlrup = ModeWriter(Ascii, vec![]);
+        trim(&cnf, &xnf, VecBackParser(temp.1), false, &mut xlrup)?;
+        check_xlrup(Ascii, cnf, &xnf, xlrup.1.into_iter())
+      };
+      run().unwrap_or_else(|e| panic!("test_{}: {}", n, e))
+    }
+  }
 }
@@ -0,0 +1,94 @@
+use std::{fmt, io};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+  /// The input could not be parsed, or its steps are not put together correctly.
+  Malformed,
+  /// The input is well-formed, but a step does not follow from the ones it cites.
+  Invalid,
+  /// Reading or writing a file failed.
+  Io,
+}
+
+/// An error in a proof or formula, with as much location information as is known.
+#[derive(Debug)]
+pub struct FratError {
+  pub kind: ErrorKind,
+  /// The kind of step, like `add` or `imply-xor`.
+  pub step: Option<&'static str>,
+  /// The ID of the step.
+  pub id: Option<u64>,
+  /// The byte offset of the step in the proof file.
+  pub offset: Option<usize>,
+  pub cause: String,
+}
+
+pub type Result<T> = std::result::Result<T, FratError>;
+
+impl FratError {
+  pub fn new(kind: ErrorKind, cause: String) -> Self {
+    FratError { kind, step: None, id: None, offset: None, cause }
+  }
+
+  /// Fill in the step kind and ID, unless they are already known.
+  pub fn at(mut self, step: &'static str, id: Option<u64>) -> Self {
+    if self.step.is_none() { self.step = Some(step); self.id = id }
+    self
+  }
+
+  /// Fill in the byte offset, unless it is already known.
+  pub fn offset(mut self, offset: Option<usize>) -> Self {
+    self.offset = self.offset.or(offset);
+    self
+  }
+
+  pub fn exit_code(&self) -> i32 {
+    match self.kind {
+      ErrorKind::Invalid => 1,
+      ErrorKind::Malformed => 3,
+      ErrorKind::Io => 4,
+    }
+  }
+}
+
+/// Report a bad command line with the expected `usage` and exit with code 2.
+pub fn usage_error(msg: &str, usage: &str) -> ! {
+  eprintln!("{}. Expected:\n\n{}", msg, usage);
+  std::process::exit(2)
+}
+
+impl From<io::Error> for FratError {
+  fn from(e: io::Error) -> Self { FratError::new(ErrorKind::Io, e.to_string()) }
+}
+
+impl fmt::Display for FratError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", match self.kind {
+      ErrorKind::Malformed => "malformed",
+      ErrorKind::Invalid => "invalid",
+      ErrorKind::Io => "I/O error",
+    })?;
+    match (self.step, self.id) {
+      (Some(step), Some(id)) => write!(f, " {} step {}", step, id)?,
+      (Some(step), None) => write!(f, " {} step", step)?,
+      _ => {}
+    }
+    if let Some(offset) = self.offset { write!(f, " at byte {}", offset)? }
+    write!(f, ": {}", self.cause)
+  }
+}
+
+/// Make a `FratError` of kind `Malformed` from a format string.
+macro_rules! malformed {
+  ($($arg:tt)*) => { $crate::error::FratError::new($crate::error::ErrorKind::Malformed, format!($($arg)*)) }
+}
+
+/// Make a `FratError` of kind `Invalid` from a format string.
+macro_rules! invalid {
+  ($($arg:tt)*) => { $crate::error::FratError::new($crate::error::ErrorKind::Invalid, format!($($arg)*)) }
+}
+
+/// Return an error made by `malformed!` or `invalid!` unless the condition holds.
+macro_rules! ensure {
+  ($cond:expr, $kind:ident!($($arg:tt)*)) => { if !$cond { return Err($kind!($($arg)*)) } }
+}
@@ -60,12 +60,13 @@ fn from_drat(mode: impl Mode, cnf: Vec<Box<[i64]>>, drat: File, frat: File) -> i
   w.flush()
 }
 
-pub fn main(mut args: impl Iterator<Item=String>) -> io::Result<()> {
-  let (_vars, cnf) = parse_dimacs(read_to_string(args.next().expect("missing input file"))?.bytes());
+pub fn main(mut args: impl Iterator<Item=String>) -> crate::Result<()> {
+  let (_vars, cnf) = parse_dimacs(read_to_string(args.next().expect("missing input file"))?.bytes())?;
   let mut drat = File::open(args.next().expect("missing proof file"))?;
   let bin = detect_binary(&mut drat)?;
   drat.seek(SeekFrom::Start(0))?;
   let frat = File::create(args.next().expect("missing output file"))?;
-  if bin { from_drat(Bin, cnf, drat, frat) }
-  else { from_drat(Ascii, cnf, drat, frat) }
+  if bin { from_drat(Bin, cnf, drat, frat)? }
+  else { from_drat(Ascii, cnf, drat, frat)? }
+  Ok(())
 }
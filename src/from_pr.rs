@@ -11,16 +11,13 @@ use crate::parser::{Mode, StepRef, Ascii, Bin, AddKind, DRATParser, DRATStep,
 use crate::perm_clause::PermClause;
 use crate::serialize::{Serialize, ModeWrite, ModeWriter};
 
-#[repr(u8)] #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)] #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 enum Assign {
-  No = 0,
+  #[default] No = 0,
   Minimized = 1,
   Assigned = 2,
 }
 
-impl Default for Assign {
-  fn default() -> Self { Self::No }
-}
 
 struct PrStep {
   assignment: MidVec<Assign>,
@@ -215,7 +212,7 @@ fn from_pr(mode: impl Mode, (vars, cnf): (usize, Vec<Box<[i64]>>),
 ) -> io::Result<()> {
   let pr = DRATParser::from(mode, BufReader::new(pr).bytes().map(Result::unwrap));
   let mut maxvar = vars.try_into().unwrap();
-  let w = &mut ModeWriter(DefaultMode::default(), BufWriter::new(frat));
+  let w = &mut ModeWriter(DefaultMode, BufWriter::new(frat));
   let mut k = 0;
   let mut ctx: Context = Context::default();
   for ls in cnf {
@@ -279,14 +276,15 @@ fn from_pr(mode: impl Mode, (vars, cnf): (usize, Vec<Box<[i64]>>),
   Ok(())
 }
 
-pub fn main(mut args: impl Iterator<Item=String>) -> io::Result<()> {
+pub fn main(mut args: impl Iterator<Item=String>) -> crate::Result<()> {
   let dimacs = args.next().expect("missing input file");
-  let cnf = parse_dimacs(read_to_string(dimacs)?.bytes());
+  let cnf = parse_dimacs(read_to_string(dimacs)?.bytes())?;
   let mut pr = File::open(args.next().expect("missing proof file"))?;
   let bin = detect_binary(&mut pr)?;
   pr.seek(SeekFrom::Start(0))?;
   let frat = File::create(args.next().expect("missing output file"))?;
   let opt = matches!(args.next().as_deref(), Some("-O"));
-  if bin { from_pr(Bin, cnf, pr, frat, opt) }
-  else { from_pr(Ascii, cnf, pr, frat, opt) }
+  if bin { from_pr(Bin, cnf, pr, frat, opt)? }
+  else { from_pr(Ascii, cnf, pr, frat, opt)? }
+  Ok(())
 }
@@ -0,0 +1,121 @@
+//! Gaussian elimination over XORs, used to find the XORs that sum to a given one
+//! when a proof step leaves out its hints.
+
+use crate::HashMap;
+use crate::xor::Xor;
+
+/// The symmetric difference of two sorted lists of IDs.
+fn sym_diff(a: &[u64], b: &[u64]) -> Vec<u64> {
+  let mut out = Vec::with_capacity(a.len() + b.len());
+  let (mut i, mut j) = (0, 0);
+  while i < a.len() && j < b.len() {
+    if a[i] < b[j] { out.push(a[i]); i += 1 }
+    else if b[j] < a[i] { out.push(b[j]); j += 1 }
+    else { i += 1; j += 1 }
+  }
+  out.extend_from_slice(&a[i..]);
+  out.extend_from_slice(&b[j..]);
+  out
+}
+
+/// An XOR together with the (sorted) IDs of the inserted XORs it is the sum of.
+struct Row {
+  xor: Xor,
+  ids: Vec<u64>,
+}
+
+/// A set of XORs in echelon form.
+#[derive(Default)]
+pub struct Gauss {
+  /// The rows by pivot, which is the smallest variable of the row.
+  rows: HashMap<i64, Row>,
+  /// A set of inserted XORs summing to `0 = 1`, if there is one.
+  contradiction: Option<Vec<u64>>,
+}
+
+impl Gauss {
+  /// Eliminate the smallest variable of `row` for as long as there is a row with it as pivot.
+  fn reduce(&self, row: &mut Row) {
+    while let Some(r) = row.xor.vars.first().and_then(|v| self.rows.get(v)) {
+      row.xor.add(&r.xor);
+      row.ids = sym_diff(&row.ids, &r.ids);
+    }
+  }
+
+  pub fn insert(&mut self, id: u64, xor: Xor) {
+    let mut row = Row { xor, ids: vec![id] };
+    self.reduce(&mut row);
+    match row.xor.vars.first() {
+      Some(&v) => { self.rows.insert(v, row); }
+      None => if row.xor.rhs && self.contradiction.is_none() { self.contradiction = Some(row.ids) }
+    }
+  }
+
+  /// The IDs of a set of inserted XORs which sums to `target`, if there is one.
+  pub fn solve(&self, target: &Xor) -> Option<Vec<u64>> {
+    let mut row = Row { xor: target.clone(), ids: vec![] };
+    self.reduce(&mut row);
+    if !row.xor.vars.is_empty() { return None }
+    // `target` is the sum of `row.ids` and `0 = row.xor.rhs`
+    if row.xor.rhs { Some(sym_diff(&row.ids, self.contradiction.as_ref()?)) } else { Some(row.ids) }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // XOR `i` is `xors[i - 1]`, as literals in the `Xor::from_lits` convention.
+  fn gauss(xors: &[&[i64]]) -> Gauss {
+    let mut g = Gauss::default();
+    for (i, ls) in xors.iter().enumerate() { g.insert(i as u64 + 1, Xor::from_lits(ls)) }
+    g
+  }
+
+  fn solve(xors: &[&[i64]], target: &[i64]) -> Option<Vec<u64>> {
+    let ids = gauss(xors).solve(&Xor::from_lits(target))?;
+    let mut sum = Xor::default();
+    for &i in &ids { sum.add(&Xor::from_lits(xors[i as usize - 1])) }
+    assert_eq!(sum, Xor::from_lits(target), "XORs {:?} do not sum to the target", ids);
+    Some(ids)
+  }
+
+  #[test]
+  fn solvable() {
+    let xors: &[&[i64]] = &[&[1, 2], &[2, 3], &[3, 4]];
+    assert_eq!(solve(xors, &[1, 4]), Some(vec![1, 2, 3]));
+    assert_eq!(solve(xors, &[-2, 4]), Some(vec![2, 3]));
+    assert_eq!(solve(xors, &[3, 4]), Some(vec![3]));
+  }
+
+  #[test]
+  fn unsolvable() {
+    let xors: &[&[i64]] = &[&[1, 2], &[2, 3]];
+    // a variable outside the XORs
+    assert_eq!(solve(xors, &[1, 4]), None);
+    // the right variables, but the wrong parity
+    assert_eq!(solve(xors, &[1, 3]), None);
+  }
+
+  #[test]
+  fn contradiction() {
+    // x1 + x2 = 1, x2 = 1 and x1 = 1 sum to 0 = 1
+    let xors: &[&[i64]] = &[&[1, 2], &[2], &[1]];
+    assert_eq!(solve(xors, &[]), Some(vec![1, 2, 3]));
+    // with the contradiction, the parity of the target no longer matters
+    assert!(solve(xors, &[1, 2]).is_some());
+    assert!(solve(xors, &[-1, 2]).is_some());
+    // but its variables do
+    assert_eq!(solve(xors, &[3]), None);
+  }
+
+  #[test]
+  fn duplicate_ids() {
+    // XOR 1 is used to reduce both XOR 2 and the target, so it cancels out
+    let xors: &[&[i64]] = &[&[1, 2], &[1, 3]];
+    assert_eq!(solve(xors, &[1, 3]), Some(vec![2]));
+    // an XOR inserted twice is only used once
+    let xors: &[&[i64]] = &[&[1, 2], &[1, 2], &[2]];
+    assert_eq!(solve(xors, &[-1]), Some(vec![1, 3]));
+  }
+}
@@ -0,0 +1,72 @@
+//! A minimal JSON writer for the `--json` reports of `stat` and `elab`.
+
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+fn string(out: &mut String, s: &str) {
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+}
+
+/// A JSON object, built one field at a time.
+#[derive(Default)]
+pub struct Obj(String);
+
+impl Obj {
+  fn key(mut self, k: &str, v: impl FnOnce(&mut String)) -> Self {
+    self.0.push(if self.0.is_empty() {'{'} else {','});
+    string(&mut self.0, k);
+    self.0.push(':');
+    v(&mut self.0);
+    self
+  }
+
+  pub fn num(self, k: &str, v: impl fmt::Display) -> Self {
+    self.key(k, |out| { let _ = write!(out, "{}", v); })
+  }
+
+  pub fn opt_num(self, k: &str, v: Option<impl fmt::Display>) -> Self {
+    match v { Some(v) => self.num(k, v), None => self.key(k, |out| out.push_str("null")) }
+  }
+
+  pub fn bool(self, k: &str, v: bool) -> Self { self.num(k, v) }
+
+  /// A duration in seconds.
+  pub fn secs(self, k: &str, v: Option<Duration>) -> Self {
+    self.opt_num(k, v.map(|d| d.as_secs_f64()))
+  }
+
+  pub fn strs<'a>(self, k: &str, v: impl IntoIterator<Item=&'a String>) -> Self {
+    self.key(k, |out| {
+      out.push('[');
+      for (i, s) in v.into_iter().enumerate() {
+        if i != 0 { out.push(',') }
+        string(out, s)
+      }
+      out.push(']');
+    })
+  }
+
+  pub fn obj(self, k: &str, v: Obj) -> Self {
+    self.key(k, |out| { let _ = write!(out, "{}", v); })
+  }
+
+  pub fn opt_obj(self, k: &str, v: Option<Obj>) -> Self {
+    match v { Some(v) => self.obj(k, v), None => self.key(k, |out| out.push_str("null")) }
+  }
+}
+
+impl fmt::Display for Obj {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.0.is_empty() { f.write_str("{}") } else { write!(f, "{}}}", self.0) }
+  }
+}
@@ -0,0 +1,42 @@
+//! Elaboration of FRAT-XOR proofs into XLRUP, and checkers for the formats involved.
+//!
+//! The `frat-xor` binary is a thin command line wrapper around this crate.
+//! [`elab::elaborate`] runs the whole pipeline with the options in [`elab::ElabOptions`];
+//! [`elab::elab`], [`elab::trim`] and the checkers can also be called separately.
+
+#![allow(clippy::upper_case_acronyms)]
+
+#[macro_use] pub mod error;
+pub mod dimacs;
+pub mod parser;
+pub mod backparser;
+pub mod perm_clause;
+mod midvec;
+mod json;
+pub mod elab;
+pub mod stat;
+pub mod dratchk;
+pub mod serialize;
+pub mod writer;
+pub mod xlrup_opt;
+pub mod capi;
+pub mod from_drat;
+pub mod strip_frat;
+pub mod drat_trim;
+pub mod from_pr;
+pub mod to_cnf;
+pub mod xor;
+pub mod gauss;
+pub mod bnn;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
+
+pub type HashMap<K, V> = std::collections::HashMap<K, V, BuildHasherDefault<DefaultHasher>>;
+pub type HashSet<K> = std::collections::HashSet<K, BuildHasherDefault<DefaultHasher>>;
+
+pub use backparser::{BackParser, Segments, StepIter, ElabStepIter};
+pub use elab::{ElabOptions, ElabReport, ElabStats, ElabTimings, TrimStats};
+pub use error::{ErrorKind, FratError, Result};
+pub use parser::{Ascii, Bin, ElabStep, FwdParser, Mode, Segment, Step, XLRUPParser, XLRUPStep, XLRUPStepRef};
+pub use writer::ProofWriter;
@@ -1,5 +1,6 @@
 use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom};
+use crate::error::{FratError, Result};
 
 pub trait BackScan {
   fn back_scan(&mut self, _: &[u8]) -> Option<usize>;
@@ -7,7 +8,7 @@ pub trait BackScan {
 
 pub trait Mode: Default {
   type BackScanState: BackScan;
-  fn bin(&self) -> bool;
+  #[allow(unused)] fn bin(&self) -> bool;
   fn new_back_scan(&self) -> Self::BackScanState;
   fn keyword(&self, it: &mut impl Iterator<Item=u8>) -> Option<u8> { it.next() }
   fn unum(&self, it: &mut impl Iterator<Item=u8>) -> Option<u64>;
@@ -93,20 +94,19 @@ pub trait Mode: Default {
           if let Some(out) = self.num(it) {
             if let Some(rem) = self.num(it) {
               if rem != 0 {
-                println!("parse error at char {} for bnn, invalid bnn format.", ch());
-                return Segment::Error();
+                return Segment::Error(malformed!("invalid bnn format").offset(Some(ch())));
               }
             }
             Segment::BnnRhs(rhs, out)
           } else {
-            Segment::Error()   
+            Segment::Error(malformed!("invalid bnn format").offset(Some(ch())))
           }
         } else {
-          Segment::Error()
+          Segment::Error(malformed!("invalid bnn format").offset(Some(ch())))
         }
       }
-      Some(k) => { println!("parse error at char {}: bad step {:?}", ch(), k as char); Segment::Error() }
-      None => { println!("parse error at char {}: bad step None", ch()); Segment::Error() } 
+      Some(k) => Segment::Error(malformed!("bad step {:?}", k as char).offset(Some(ch()))),
+      None => Segment::Error(malformed!("bad step None").offset(Some(ch()))),
     }
   }
 
@@ -116,11 +116,8 @@ pub trait Mode: Default {
 
   fn segment(&self, ch: impl Fn() -> usize, mut it: impl Iterator<Item=u8>) -> Segment {
     let seg = self.segment_mut(&ch, &mut it);
-    if let Segment::Error() = seg {
-       // println!("parse error");
-    } else if !self.check_empty(it) {
-       println!("parse error at char {}: segment has trailing characters", ch());
-       return Segment::Error();
+    if !matches!(seg, Segment::Error(_)) && !self.check_empty(it) {
+      return Segment::Error(malformed!("segment has trailing characters").offset(Some(ch())))
     }
     seg
   }
@@ -149,7 +146,7 @@ pub enum Segment {
   BnnLhs(u64, Vec<i64>),
   BnnRhs(i64, i64),
   BnnImply(),
-  Error(),
+  Error(FratError),
 }
 
 #[derive(Default)] pub struct Bin;
@@ -212,13 +209,13 @@ impl Ascii {
   }
   fn initial_neg(it: &mut impl Iterator<Item=u8>) -> (bool, Option<u8>) {
     match Ascii::spaces(it) {
-      Some(c) if c == b'-' => (true, it.next()),
+      Some(b'-') => (true, it.next()),
       o => (false, o)
     }
   }
 
   fn parse_num(peek: Option<u8>, it: &mut impl Iterator<Item=u8>) -> Option<u64> {
-    let mut val = (peek? as char).to_digit(10).unwrap() as u64;
+    let mut val = (peek? as char).to_digit(10)? as u64;
 		while let Some(parsed) = it.next().and_then(|c| (c as char).to_digit(10)) {
 			val *= 10;
       val += parsed as u64;
@@ -378,7 +375,7 @@ impl<M, I> LRATParser<M, I> {
 
 #[derive(Debug)]
 pub enum LRATStep {
-	Comment(String),
+	Comment(#[allow(unused)] String),
 	Add(AddStep, Vec<i64>),
 	Del(Vec<i64>)
 }
@@ -398,6 +395,297 @@ impl<M: Mode, I: Iterator<Item=u8>> Iterator for LRATParser<M, I> {
 	}
 }
 
+/// A parser for XLRUP, the output format of `elab`, in ASCII or binary.
+/// Malformed steps are reported with their byte offset, after which the parser stops.
+pub struct XLRUPParser<M, I> {
+  mode: M,
+  it: I,
+  /// The number of bytes read so far.
+  pos: usize,
+  /// The last clause ID, which binary comments and deletions inherit.
+  last: u64,
+  done: bool,
+}
+
+impl<M, I> XLRUPParser<M, I> {
+  pub fn from(mode: M, it: I) -> Self { XLRUPParser {mode, it, pos: 0, last: 0, done: false} }
+
+  /// Set the clause ID used for binary comments and deletions before the first added clause,
+  /// normally the number of clauses in the formula.
+  pub fn start(mut self, k: u64) -> Self { self.last = k; self }
+}
+
+/// A step of an XLRUP proof. The ID in `Comment` and `Del` is the last clause ID;
+/// see `XLRUPStepRef`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XLRUPStep {
+  Comment(u64, String),
+  Add(u64, Vec<i64>, Vec<i64>),
+  Del(u64, Vec<u64>),
+  OrigXor(u64, Vec<i64>),
+  AddXor(u64, Vec<i64>, Vec<i64>, Vec<u64>),
+  DelXor(Vec<u64>),
+  Imply(u64, Vec<i64>, Vec<i64>),
+  ImplyXor(u64, Vec<i64>, Vec<i64>),
+  OrigBnn(u64, Vec<i64>, i64, i64),
+  AddBnn(u64, Vec<i64>, i64, i64, Vec<i64>),
+  DelBnn(Vec<u64>),
+  BnnImply(u64, Vec<i64>, u64, Vec<u64>),
+}
+
+impl XLRUPStep {
+  pub fn as_ref(&self) -> XLRUPStepRef<'_> {
+    match *self {
+      XLRUPStep::Comment(k, ref s) => XLRUPStepRef::Comment(k, s),
+      XLRUPStep::Add(i, ref ls, ref is) => XLRUPStepRef::Add(i, ls, is),
+      XLRUPStep::Del(k, ref cs) => XLRUPStepRef::Del(k, cs),
+      XLRUPStep::OrigXor(i, ref ls) => XLRUPStepRef::OrigXor(i, ls),
+      XLRUPStep::AddXor(i, ref ls, ref is, ref u) => XLRUPStepRef::AddXor(i, ls, is, u),
+      XLRUPStep::DelXor(ref xs) => XLRUPStepRef::DelXor(xs),
+      XLRUPStep::Imply(i, ref ls, ref is) => XLRUPStepRef::Imply(i, ls, is),
+      XLRUPStep::ImplyXor(i, ref ls, ref is) => XLRUPStepRef::ImplyXor(i, ls, is),
+      XLRUPStep::OrigBnn(i, ref ls, rhs, out) => XLRUPStepRef::OrigBnn(i, ls, rhs, out),
+      XLRUPStep::AddBnn(i, ref ls, rhs, out, ref is) => XLRUPStepRef::AddBnn(i, ls, rhs, out, is),
+      XLRUPStep::DelBnn(ref bs) => XLRUPStepRef::DelBnn(bs),
+      XLRUPStep::BnnImply(i, ref ls, b, ref u) => XLRUPStepRef::BnnImply(i, ls, b, u),
+    }
+  }
+
+  /// The kind and ID of the step, for error messages.
+  pub fn kind(&self) -> (&'static str, Option<u64>) { self.as_ref().kind() }
+}
+
+impl<M: Mode, I: Iterator<Item=u8>> XLRUPParser<M, I> {
+  fn byte(&mut self) -> Option<u8> {
+    let c = self.it.next()?;
+    self.pos += 1;
+    Some(c)
+  }
+
+  /// Run `f` on the remaining input, with the byte which was already read prepended.
+  fn with_peek<T>(&mut self, k: u8, f: impl FnOnce(&mut dyn Iterator<Item=u8>) -> Option<T>) -> Result<T> {
+    let pos = &mut self.pos;
+    let it = &mut self.it;
+    f(&mut Some(k).into_iter().chain(it.inspect(|_| *pos += 1))).ok_or_else(bad_number)
+  }
+
+  fn keyword(&mut self) -> Result<u8> {
+    let (mode, pos) = (&self.mode, &mut self.pos);
+    mode.keyword(&mut self.it.by_ref().inspect(|_| *pos += 1)).ok_or_else(unexpected_end)
+  }
+
+  fn unum(&mut self) -> Result<u64> {
+    let (mode, pos) = (&self.mode, &mut self.pos);
+    mode.unum(&mut self.it.by_ref().inspect(|_| *pos += 1)).ok_or_else(bad_number)
+  }
+
+  fn num(&mut self) -> Result<i64> {
+    let (mode, pos) = (&self.mode, &mut self.pos);
+    mode.num(&mut self.it.by_ref().inspect(|_| *pos += 1)).ok_or_else(bad_number)
+  }
+
+  fn uvec(&mut self) -> Result<Vec<u64>> {
+    let mut vec = vec![];
+    loop { match self.unum()? { 0 => return Ok(vec), i => vec.push(i) } }
+  }
+
+  fn ivec(&mut self) -> Result<Vec<i64>> {
+    let mut vec = vec![];
+    loop { match self.num()? { 0 => return Ok(vec), i => vec.push(i) } }
+  }
+
+  fn comment(&mut self) -> String {
+    let (mode, pos) = (&self.mode, &mut self.pos);
+    mode.comment(&mut self.it.by_ref().inspect(|_| *pos += 1))
+  }
+
+  /// Parses `lits 0 cutoff [out] 0`.
+  fn bnn(&mut self) -> Result<(Vec<i64>, i64, i64)> {
+    let ls = self.ivec()?;
+    let rhs = self.num()?;
+    match self.num()? {
+      0 => Ok((ls, rhs, 0)),
+      out => {
+        ensure!(self.num()? == 0, malformed!("invalid bnn format"));
+        Ok((ls, rhs, out))
+      }
+    }
+  }
+
+  /// Parses `IDs [u CIDs] 0`.
+  fn hints_units(&mut self) -> Result<(Vec<i64>, Vec<u64>)> {
+    let mut is = vec![];
+    loop {
+      match self.keyword()? {
+        b'u' => return Ok((is, self.uvec()?)),
+        k => match self.with_peek(k, |mut it| Ascii.num(&mut it))? {
+          0 => return Ok((is, vec![])),
+          i => is.push(i)
+        }
+      }
+    }
+  }
+
+  /// Parse one step, given a function which parses the step at the current position.
+  fn next_with(&mut self, f: impl FnOnce(&mut Self) -> Result<Option<XLRUPStep>>) -> Option<Result<XLRUPStep>> {
+    if self.done { return None }
+    let start = self.pos;
+    match f(self) {
+      Ok(Some(s)) => {
+        if let XLRUPStep::Add(i, _, _) | XLRUPStep::Imply(i, _, _) |
+          XLRUPStep::BnnImply(i, _, _, _) = s { self.last = i }
+        Some(Ok(s))
+      }
+      Ok(None) => None,
+      Err(e) => {
+        self.done = true;
+        Some(Err(e.offset(Some(start))))
+      }
+    }
+  }
+}
+
+fn unexpected_end() -> FratError { malformed!("unexpected end of proof") }
+fn bad_number() -> FratError { malformed!("expected a number") }
+
+impl<I: Iterator<Item=u8>> Iterator for XLRUPParser<Ascii, I> {
+  type Item = Result<XLRUPStep>;
+  fn next(&mut self) -> Option<Result<XLRUPStep>> {
+    self.next_with(|p| {
+      let k = match p.keyword() { Ok(k) => k, Err(_) => return Ok(None) };
+      Ok(Some(match k {
+        b'o' => match p.keyword()? {
+          b'x' => XLRUPStep::OrigXor(p.unum()?, p.ivec()?),
+          b'b' => {
+            let i = p.unum()?;
+            let (ls, rhs, out) = p.bnn()?;
+            XLRUPStep::OrigBnn(i, ls, rhs, out)
+          }
+          k => return Err(malformed!("bad step: o {:?}", k as char))
+        }
+        b'x' => match p.keyword()? {
+          b'd' => XLRUPStep::DelXor(p.uvec()?),
+          k => {
+            let i = p.with_peek(k, |mut it| Ascii.unum(&mut it))?;
+            let ls = p.ivec()?;
+            let (is, u) = p.hints_units()?;
+            XLRUPStep::AddXor(i, ls, is, u)
+          }
+        }
+        b'i' => match p.keyword()? {
+          b'x' => XLRUPStep::ImplyXor(p.unum()?, p.ivec()?, p.ivec()?),
+          b'c' => match p.byte() {
+            Some(b'x') => XLRUPStep::Imply(p.unum()?, p.ivec()?, p.ivec()?),
+            Some(b'b') => {
+              let (i, ls, bid) = (p.unum()?, p.ivec()?, p.unum()?);
+              let u = match p.keyword()? {
+                b'u' => p.uvec()?,
+                b'0' => vec![],
+                k => return Err(malformed!("bad step: i cb {} {:?}", i, k as char))
+              };
+              XLRUPStep::BnnImply(i, ls, bid, u)
+            }
+            k => return Err(malformed!("bad step: i c{:?}", k.map(|k| k as char)))
+          }
+          k => return Err(malformed!("bad step: i {:?}", k as char))
+        }
+        b'b' => match p.keyword()? {
+          b'd' => XLRUPStep::DelBnn(p.uvec()?),
+          k => {
+            let i = p.with_peek(k, |mut it| Ascii.unum(&mut it))?;
+            let (ls, rhs, out) = p.bnn()?;
+            XLRUPStep::AddBnn(i, ls, rhs, out, p.ivec()?)
+          }
+        }
+        k => {
+          let i = p.with_peek(k, |mut it| Ascii.unum(&mut it))?;
+          match p.keyword()? {
+            b'c' => XLRUPStep::Comment(i, p.comment()),
+            b'd' => XLRUPStep::Del(i, p.uvec()?),
+            k => {
+              let ls = match p.with_peek(k, |mut it| Ascii.num(&mut it))? {
+                0 => vec![],
+                l => { let mut ls = vec![l]; ls.extend(p.ivec()?); ls }
+              };
+              XLRUPStep::Add(i, ls, p.ivec()?)
+            }
+          }
+        }
+      }))
+    })
+  }
+}
+
+impl<I: Iterator<Item=u8>> Iterator for XLRUPParser<Bin, I> {
+  type Item = Result<XLRUPStep>;
+  fn next(&mut self) -> Option<Result<XLRUPStep>> {
+    self.next_with(|p| {
+      let k = match p.byte() { Some(k) => k, None => return Ok(None) };
+      Ok(Some(match k {
+        b'c' => XLRUPStep::Comment(p.last, p.comment()),
+        b'a' => XLRUPStep::Add(p.unum()?, p.ivec()?, p.ivec()?),
+        b'd' => XLRUPStep::Del(p.last, p.uvec()?),
+        b'o' => XLRUPStep::OrigXor(p.unum()?, p.ivec()?),
+        b'x' => XLRUPStep::AddXor(p.unum()?, p.ivec()?, p.ivec()?, p.uvec()?),
+        b'e' => XLRUPStep::DelXor(p.uvec()?),
+        b'i' => XLRUPStep::Imply(p.unum()?, p.ivec()?, p.ivec()?),
+        b'j' => XLRUPStep::ImplyXor(p.unum()?, p.ivec()?, p.ivec()?),
+        b'O' => {
+          let i = p.unum()?;
+          let (ls, rhs, out) = p.bnn()?;
+          XLRUPStep::OrigBnn(i, ls, rhs, out)
+        }
+        b'b' => {
+          let i = p.unum()?;
+          let (ls, rhs, out) = p.bnn()?;
+          XLRUPStep::AddBnn(i, ls, rhs, out, p.ivec()?)
+        }
+        b'E' => XLRUPStep::DelBnn(p.uvec()?),
+        b'I' => XLRUPStep::BnnImply(p.unum()?, p.ivec()?, p.unum()?, p.uvec()?),
+        k => return Err(malformed!("bad step {:?}", k as char))
+      }))
+    })
+  }
+}
+
+/// A step of an XLRUP proof to be written. The ID in `Comment` and `Del` is
+/// the last clause ID, which the ASCII format puts at the start of the line.
+#[derive(Debug, Copy, Clone)]
+pub enum XLRUPStepRef<'a> {
+  Comment(u64, &'a str),
+  Add(u64, &'a [i64], &'a [i64]),
+  Del(u64, &'a [u64]),
+  OrigXor(u64, &'a [i64]),
+  AddXor(u64, &'a [i64], &'a [i64], &'a [u64]),
+  DelXor(&'a [u64]),
+  Imply(u64, &'a [i64], &'a [i64]),
+  ImplyXor(u64, &'a [i64], &'a [i64]),
+  OrigBnn(u64, &'a [i64], i64, i64),
+  AddBnn(u64, &'a [i64], i64, i64, &'a [i64]),
+  DelBnn(&'a [u64]),
+  BnnImply(u64, &'a [i64], u64, &'a [u64]),
+}
+
+impl<'a> XLRUPStepRef<'a> {
+  /// The kind and ID of the step, for error messages.
+  pub fn kind(&self) -> (&'static str, Option<u64>) {
+    match *self {
+      XLRUPStepRef::Comment(_, _) => ("comment", None),
+      XLRUPStepRef::Add(i, _, _) => ("add", Some(i)),
+      XLRUPStepRef::Del(_, _) => ("del", None),
+      XLRUPStepRef::OrigXor(i, _) => ("orig-xor", Some(i)),
+      XLRUPStepRef::AddXor(i, _, _, _) => ("add-xor", Some(i)),
+      XLRUPStepRef::DelXor(_) => ("del-xor", None),
+      XLRUPStepRef::Imply(i, _, _) => ("imply", Some(i)),
+      XLRUPStepRef::ImplyXor(i, _, _) => ("imply-xor", Some(i)),
+      XLRUPStepRef::OrigBnn(i, _, _, _) => ("orig-bnn", Some(i)),
+      XLRUPStepRef::AddBnn(i, _, _, _, _) => ("add-bnn", Some(i)),
+      XLRUPStepRef::DelBnn(_) => ("del-bnn", None),
+      XLRUPStepRef::BnnImply(i, _, _, _) => ("bnn-imply", Some(i)),
+    }
+  }
+}
+
 pub struct DRATParser<M, I> {mode: M, it: I}
 
 impl<M, I> DRATParser<M, I> {
@@ -429,7 +717,7 @@ pub enum ProofRef<'a> {
 }
 
 impl Proof {
-  pub fn as_ref(&self) -> ProofRef {
+  pub fn as_ref(&self) -> ProofRef<'_> {
     match self {
       Proof::LRAT(v) => ProofRef::LRAT(v),
       Proof::Unit(v) => ProofRef::Unit(v),
@@ -524,6 +812,32 @@ pub enum Step {
   FinalBnn(u64, Vec<i64>, i64, i64),
 }
 
+impl Step {
+  /// The kind and ID of the step, for error messages.
+  pub fn kind(&self) -> (&'static str, Option<u64>) {
+    match *self {
+      Step::Comment(_) => ("comment", None),
+      Step::Orig(i, _) => ("orig", Some(i)),
+      Step::Add(i, _, _) => ("add", Some(i)),
+      Step::Del(i, _) => ("del", Some(i)),
+      Step::Reloc(_) => ("reloc", None),
+      Step::Final(i, _) => ("final", Some(i)),
+      Step::Todo(i) => ("todo", Some(i)),
+      Step::OrigXor(i, _) => ("orig-xor", Some(i)),
+      Step::AddXor(i, _, _, _) => ("add-xor", Some(i)),
+      Step::DelXor(i, _) => ("del-xor", Some(i)),
+      Step::Imply(i, _, _) => ("imply", Some(i)),
+      Step::ImplyXor(i, _, _) => ("imply-xor", Some(i)),
+      Step::FinalXor(i, _) => ("final-xor", Some(i)),
+      Step::OrigBnn(i, _, _, _) => ("orig-bnn", Some(i)),
+      Step::AddBnn(i, _, _, _, _) => ("add-bnn", Some(i)),
+      Step::BnnImply(i, _, _, _) => ("bnn-imply", Some(i)),
+      Step::DelBnn(i, _, _, _) => ("del-bnn", Some(i)),
+      Step::FinalBnn(i, _, _, _) => ("final-bnn", Some(i)),
+    }
+  }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum StepRef<'a> {
   Comment(&'a str),
@@ -605,11 +919,33 @@ pub enum ElabStep {
   Imply(u64, Vec<i64>, Vec<i64>),
   ImplyXor(u64, Vec<i64>, Vec<i64>),
   OrigBnn(u64, Vec<i64>, i64, i64),
-  AddBnn(u64, Vec<i64>, i64, i64, Vec<i64>),
+  #[allow(unused)] AddBnn(u64, Vec<i64>, i64, i64, Vec<i64>),
   BnnImply(u64, Vec<i64>, Vec<i64>, Option<Proof>),
   DelBnn(u64),
 }
 
+impl ElabStep {
+  /// The kind and ID of the step, for error messages.
+  pub fn kind(&self) -> (&'static str, Option<u64>) {
+    match *self {
+      ElabStep::Comment(_) => ("comment", None),
+      ElabStep::Orig(i, _) => ("orig", Some(i)),
+      ElabStep::Add(i, _, _) => ("add", Some(i)),
+      ElabStep::Reloc(_) => ("reloc", None),
+      ElabStep::Del(i) => ("del", Some(i)),
+      ElabStep::OrigXor(i, _) => ("orig-xor", Some(i)),
+      ElabStep::AddXor(i, _, _, _) => ("add-xor", Some(i)),
+      ElabStep::DelXor(i) => ("del-xor", Some(i)),
+      ElabStep::Imply(i, _, _) => ("imply", Some(i)),
+      ElabStep::ImplyXor(i, _, _) => ("imply-xor", Some(i)),
+      ElabStep::OrigBnn(i, _, _, _) => ("orig-bnn", Some(i)),
+      ElabStep::AddBnn(i, _, _, _, _) => ("add-bnn", Some(i)),
+      ElabStep::BnnImply(i, _, _, _) => ("bnn-imply", Some(i)),
+      ElabStep::DelBnn(i) => ("del-bnn", Some(i)),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub enum ElabStepRef<'a> {
   Comment(&'a str),
@@ -629,7 +965,7 @@ pub enum ElabStepRef<'a> {
 }
 
 impl ElabStep {
-  pub fn as_ref(&self) -> ElabStepRef {
+  pub fn as_ref(&self) -> ElabStepRef<'_> {
     match *self {
       ElabStep::Comment(ref s) => ElabStepRef::Comment(s),
       ElabStep::Orig(i, ref v) => ElabStepRef::Orig(i, v),
@@ -710,3 +1046,50 @@ impl<M: Mode> Iterator for FwdParser<M> {
     Some(self.mode.segment_mut(|| start, inner))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::serialize::{Serialize, ModeWriter};
+
+  // Comment and deletion IDs follow the last added clause, as the binary format requires
+  fn steps() -> Vec<XLRUPStep> {
+    use XLRUPStep::*;
+    vec![
+      Comment(3, "start".into()),
+      OrigXor(1, vec![1, -2, 3]),
+      AddXor(2, vec![1, 2], vec![1], vec![]),
+      AddXor(3, vec![-3], vec![1, 2], vec![1, 2]),
+      Imply(4, vec![1, -3], vec![2]),
+      ImplyXor(4, vec![1, 3], vec![1, 4]),
+      Del(4, vec![1, 2]),
+      DelXor(vec![1, 2]),
+      OrigBnn(1, vec![1, 2, 3], 2, 0),
+      OrigBnn(2, vec![1, -2, 3], 2, 4),
+      AddBnn(3, vec![1, 2], 1, 0, vec![1, 4]),
+      AddBnn(4, vec![-1, 2], 1, 3, vec![2, 4]),
+      BnnImply(5, vec![1, 2], 1, vec![]),
+      BnnImply(6, vec![-1], 2, vec![4, 5]),
+      DelBnn(vec![1, 3]),
+      Comment(6, "bnns done".into()),
+      Add(7, vec![1, 2, -3], vec![4, 5, 6]),
+      Add(8, vec![], vec![7, 4, 6]),
+      Del(8, vec![7]),
+    ]
+  }
+
+  fn round_trip<M: Mode>() where
+    for<'a> XLRUPStepRef<'a>: Serialize<M>,
+    XLRUPParser<M, std::vec::IntoIter<u8>>: Iterator<Item=Result<XLRUPStep>>,
+  {
+    let mut w = ModeWriter(M::default(), vec![]);
+    for s in steps() { s.write(&mut w).unwrap() }
+    let parsed = XLRUPParser::from(M::default(), w.1.into_iter()).start(3)
+      .collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(parsed, steps());
+  }
+
+  #[test] fn xlrup_ascii_round_trip() { round_trip::<Ascii>() }
+
+  #[test] fn xlrup_bin_round_trip() { round_trip::<Bin>() }
+}
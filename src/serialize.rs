@@ -1,7 +1,7 @@
 use arrayvec::ArrayVec;
 use std::io::{self, Write};
 use super::parser::{Ascii, Bin, DefaultMode,
-  Step, StepRef, AddStep, AddStepRef, ElabStep, ElabStepRef, ProofRef};
+  Step, StepRef, AddStep, AddStepRef, ElabStep, ElabStepRef, ProofRef, XLRUPStep, XLRUPStepRef};
 
 pub trait ModeWrite<M=DefaultMode>: Write {}
 
@@ -129,9 +129,9 @@ impl<'a> Serialize<Bin> for StepRef<'a> {
         ((b'a', (0u8, b'x')), (idx, vec)).write(w)?;
         if let Some(ProofRef::LRAT(steps)) = pf {
           (b'l', steps).write(w)?;
-          if let Some(ProofRef::Unit(units)) = uf {
-            (b'u', units).write(w)?;
-          }
+        }
+        if let Some(ProofRef::Unit(units)) = uf {
+          (b'u', units).write(w)?;
         }
         write!(w, "")
       },
@@ -231,9 +231,9 @@ impl<'a> Serialize<Ascii> for StepRef<'a> {
         write!(w, "a x {}  ", idx)?; vec.write(w)?;
         if let Some(ProofRef::LRAT(steps)) = pf {
           write!(w, "  l ")?; steps.write(w)?;
-          if let Some(ProofRef::Unit(units)) = uf {
-            write!(w, " u ")?; units.write(w)?;
-          }
+        }
+        if let Some(ProofRef::Unit(units)) = uf {
+          write!(w, " u ")?; units.write(w)?;
         }
         writeln!(w)
       }
@@ -392,3 +392,88 @@ impl<M> Serialize<M> for ElabStep where for<'a> ElabStepRef<'a>: Serialize<M> {
     self.as_ref().write(w)
   }
 }
+
+impl<'a> Serialize<Bin> for XLRUPStepRef<'a> {
+  fn write(&self, w: &mut impl ModeWrite<Bin>) -> io::Result<()> {
+    fn bnn(w: &mut impl ModeWrite<Bin>, rhs: i64, out: i64) -> io::Result<()> {
+      if out == 0 { (rhs, 0u8).write(w) } else { ((rhs, out), 0u8).write(w) }
+    }
+    match *self {
+      XLRUPStepRef::Comment(_, s) => (b'c', s).write(w),
+      XLRUPStepRef::Add(idx, vec, steps) => (b'a', (idx, (vec, steps))).write(w),
+      XLRUPStepRef::Del(_, idxs) => (b'd', idxs).write(w),
+      XLRUPStepRef::OrigXor(idx, vec) => (b'o', (idx, vec)).write(w),
+      XLRUPStepRef::AddXor(idx, vec, steps, units) => (b'x', ((idx, vec), (steps, units))).write(w),
+      XLRUPStepRef::DelXor(idxs) => (b'e', idxs).write(w),
+      XLRUPStepRef::Imply(idx, vec, steps) => (b'i', (idx, (vec, steps))).write(w),
+      XLRUPStepRef::ImplyXor(idx, vec, steps) => (b'j', (idx, (vec, steps))).write(w),
+      XLRUPStepRef::OrigBnn(idx, vec, rhs, out) => {
+        (b'O', (idx, vec)).write(w)?; bnn(w, rhs, out)
+      }
+      XLRUPStepRef::AddBnn(idx, vec, rhs, out, steps) => {
+        (b'b', (idx, vec)).write(w)?; bnn(w, rhs, out)?; steps.write(w)
+      }
+      XLRUPStepRef::DelBnn(idxs) => (b'E', idxs).write(w),
+      XLRUPStepRef::BnnImply(idx, vec, bnn, units) => (b'I', ((idx, vec), (bnn, units))).write(w),
+    }
+  }
+}
+
+impl<M> Serialize<M> for XLRUPStep where for<'a> XLRUPStepRef<'a>: Serialize<M> {
+  fn write(&self, w: &mut impl ModeWrite<M>) -> io::Result<()> { self.as_ref().write(w) }
+}
+
+impl<'a> Serialize<Ascii> for XLRUPStepRef<'a> {
+  fn write(&self, w: &mut impl ModeWrite<Ascii>) -> io::Result<()> {
+    fn units(w: &mut impl ModeWrite<Ascii>, units: &[u64]) -> io::Result<()> {
+      if !units.is_empty() {
+        write!(w, "u ")?;
+        for u in units { write!(w, "{} ", u)? }
+      }
+      write!(w, "0")
+    }
+    fn bnn(w: &mut impl ModeWrite<Ascii>, rhs: i64, out: i64) -> io::Result<()> {
+      if out == 0 { write!(w, " {} 0", rhs) } else { write!(w, " {} {} 0", rhs, out) }
+    }
+    match *self {
+      XLRUPStepRef::Comment(k, s) => writeln!(w, "{} c {}", k, s),
+      XLRUPStepRef::Add(idx, vec, steps) => {
+        write!(w, "{} ", idx)?; vec.write(w)?; write!(w, " ")?; steps.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::Del(k, idxs) => {
+        write!(w, "{} d ", k)?; idxs.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::OrigXor(idx, vec) => {
+        write!(w, "o x {} ", idx)?; vec.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::AddXor(idx, vec, steps, us) => {
+        write!(w, "x {} ", idx)?; vec.write(w)?; write!(w, " ")?;
+        for i in steps { write!(w, "{} ", i)? }
+        units(w, us)?; writeln!(w)
+      }
+      XLRUPStepRef::DelXor(idxs) => {
+        write!(w, "x d ")?; idxs.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::Imply(idx, vec, steps) => {
+        write!(w, "i cx {} ", idx)?; vec.write(w)?; write!(w, " ")?; steps.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::ImplyXor(idx, vec, steps) => {
+        write!(w, "i x {} ", idx)?; vec.write(w)?; write!(w, " ")?; steps.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::OrigBnn(idx, vec, rhs, out) => {
+        write!(w, "o b {} ", idx)?; vec.write(w)?; bnn(w, rhs, out)?; writeln!(w)
+      }
+      XLRUPStepRef::AddBnn(idx, vec, rhs, out, steps) => {
+        write!(w, "b {} ", idx)?; vec.write(w)?; bnn(w, rhs, out)?;
+        write!(w, " ")?; steps.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::DelBnn(idxs) => {
+        write!(w, "b d ")?; idxs.write(w)?; writeln!(w)
+      }
+      XLRUPStepRef::BnnImply(idx, vec, bnn, us) => {
+        write!(w, "i cb {} ", idx)?; vec.write(w)?; write!(w, " {} ", bnn)?;
+        units(w, us)?; writeln!(w)
+      }
+    }
+  }
+}
@@ -1,58 +1,154 @@
 use std::fs::File;
 use std::process::exit;
-use std::io;
+use crate::error::{Result, usage_error};
 use crate::HashMap;
 use crate::parser::{detect_binary, Mode, Ascii, Bin};
 use crate::backparser::*;
+use crate::xor::Xor;
+use crate::json::Obj;
 
 type Clause = Vec<i64>;
+type Bnn = (Vec<i64>, i64, i64);
+
+fn bnn(mut lits: Vec<i64>, rhs: i64, out: i64) -> Bnn {
+  lits.sort_unstable();
+  (lits, rhs, out)
+}
 
 fn subsumes(clause: &[i64], clause2: &[i64]) -> bool {
   clause2.iter().all(|lit2| clause.contains(lit2))
 }
 
-pub fn check_proof(mode: impl Mode, proof: File) -> io::Result<()> {
+/// Step counts and well-formedness problems of a FRAT-XOR proof.
+#[derive(Clone, Debug, Default)]
+pub struct ProofStats {
+  pub orig: i64,
+  pub added: i64,
+  pub deleted: i64,
+  pub finalized: i64,
+  pub orig_xor: i64,
+  pub add_xor: i64,
+  pub del_xor: i64,
+  pub imply: i64,
+  pub imply_xor: i64,
+  pub fin_xor: i64,
+  pub orig_bnn: i64,
+  pub add_bnn: i64,
+  pub del_bnn: i64,
+  pub bnn_imply: i64,
+  pub fin_bnn: i64,
+  /// Added clauses without a proof.
+  pub missing: i64,
+  /// The most common todo types and their counts, most common first.
+  pub todos: Vec<(u64, i64)>,
+  pub dirty_orig: i64,
+  pub dirty_add: i64,
+  pub dirty_imply: i64,
+  pub dirty_bnn_imply: i64,
+  /// XORs introduced but never finalized.
+  pub dirty_xor: i64,
+  /// BNNs introduced but never finalized.
+  pub dirty_bnn: i64,
+  /// Clauses, XORs and BNNs deleted or finalized twice.
+  pub double_del: i64,
+  pub double_fin: i64,
+  /// Clauses deleted or finalized but never introduced.
+  pub unjustified: usize,
+  /// XORs deleted or finalized but never introduced.
+  pub unjustified_xor: usize,
+  /// BNNs deleted or finalized but never introduced.
+  pub unjustified_bnn: usize,
+  /// Hints naming an XOR that is not live at the step.
+  pub inactive_xor_hints: i64,
+  /// Hints naming a BNN that is not live at the step.
+  pub inactive_bnn_hints: i64,
+  /// Steps whose clause does not match the deletion or finalization of the same ID.
+  pub mismatches: Vec<String>,
+}
+
+impl ProofStats {
+  /// True if the proof has no well-formedness problems.
+  pub fn is_ok(&self) -> bool {
+    self.mismatches.is_empty() &&
+    self.dirty_orig == 0 && self.dirty_add == 0 && self.dirty_imply == 0 && self.dirty_bnn_imply == 0 &&
+    self.dirty_xor == 0 && self.dirty_bnn == 0 && self.double_del == 0 && self.double_fin == 0 &&
+    self.unjustified == 0 && self.unjustified_xor == 0 && self.unjustified_bnn == 0 &&
+    self.inactive_xor_hints == 0 && self.inactive_bnn_hints == 0
+  }
+
+  /// The statistics as a JSON object, as printed by `frat-xor stat --json`.
+  pub fn to_json(&self) -> String {
+    let todos = self.todos.iter().fold(Obj::default(), |o, (k, v)| o.num(&k.to_string(), v));
+    Obj::default()
+      .obj("clauses", Obj::default()
+        .num("orig", self.orig).num("added", self.added)
+        .num("implied_by_xor", self.imply).num("implied_by_bnn", self.bnn_imply)
+        .num("deleted", self.deleted).num("finalized", self.finalized))
+      .obj("xors", Obj::default()
+        .num("orig", self.orig_xor).num("added", self.add_xor).num("implied", self.imply_xor)
+        .num("deleted", self.del_xor).num("finalized", self.fin_xor))
+      .obj("bnns", Obj::default()
+        .num("orig", self.orig_bnn).num("added", self.add_bnn)
+        .num("deleted", self.del_bnn).num("finalized", self.fin_bnn))
+      .num("missing", self.missing)
+      .obj("todos", todos)
+      .obj("never_finalized", Obj::default()
+        .num("orig", self.dirty_orig).num("added", self.dirty_add).num("implied", self.dirty_imply)
+        .num("bnn_implied", self.dirty_bnn_imply).num("xors", self.dirty_xor).num("bnns", self.dirty_bnn))
+      .num("double_del", self.double_del)
+      .num("double_fin", self.double_fin)
+      .obj("unjustified", Obj::default()
+        .num("clauses", self.unjustified).num("xors", self.unjustified_xor).num("bnns", self.unjustified_bnn))
+      .obj("inactive_hints", Obj::default()
+        .num("xors", self.inactive_xor_hints).num("bnns", self.inactive_bnn_hints))
+      .strs("mismatches", &self.mismatches)
+      .bool("ok", self.is_ok())
+      .to_string()
+  }
+}
+
+pub fn check_proof(mode: impl Mode, proof: File) -> Result<ProofStats> {
   let mut bp = StepIter(BackParser::new(mode, proof)?).peekable();
-  let (mut orig, mut added, mut deleted, mut fin, mut _orig_xor, mut _add_xor, mut _del_xor, mut _imply, mut _imply_xor, mut _fin_xor, mut _orig_bnn, mut _add_bnn, mut _del_bnn, mut _bnn_imply, mut _fin_bnn) = (0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64, 0i64);
-  let (mut dirty_orig, mut dirty_add, mut dirty_imply, mut dirty_bnn_imply, mut double_del, mut double_fin) = (0i64, 0i64, 0i64, 0i64, 0i64, 0i64);
-  let mut missing = 0i64;
+  let mut st = ProofStats::default();
   let mut active: HashMap<u64, (bool, Clause)> = HashMap::default();
+  let mut active_xor: HashMap<u64, Xor> = HashMap::default();
+  let mut active_bnn: HashMap<u64, Bnn> = HashMap::default();
   let mut todos = HashMap::default();
-  let mut bad = false;
   while let Some(s) = bp.next() {
     // println!("{:?}", s);
+    let s = s?;
+    let (kind, id) = s.kind();
+    let bad_hint = |h: u64| malformed!("hint {} is not an active clause", h).at(kind, id);
     match s {
       Step::Comment(_) => {}
       Step::Orig(i, lits) => {
-        orig += 1;
+        st.orig += 1;
         match active.remove(&i) {
           None => {
-            dirty_orig += 1;
+            st.dirty_orig += 1;
             // eprintln!("original clause {} {:?} never finalized", i, lits);
           },
           Some((_, lits2)) => if !subsumes(&lits2, &lits) {
-            eprintln!("orig step {}: added {:?}, removed {:?}", i, lits, lits2);
-            bad = true;
+            st.mismatches.push(format!("orig step {}: added {:?}, removed {:?}", i, lits, lits2));
           }
         }
       },
       Step::Add(i, step, p) => {
-        added += 1;
-        if p.is_none() { missing += 1 }
-        if let Some(Step::Todo(_)) = bp.peek() {} else if p.is_none() {
+        st.added += 1;
+        if p.is_none() { st.missing += 1 }
+        if let Some(Ok(Step::Todo(_))) = bp.peek() {} else if p.is_none() {
           *todos.entry(0).or_insert(0i64) += 1;
           // eprintln!("added clause {} {:?} has no proof and no todo", i, lits);
         }
         if let Some((need, lits2)) = active.remove(&i) {
           let lits = step.parse().lemma();
           if !subsumes(&lits2, lits) {
-            eprintln!("add step {}: added {:?}, removed {:?}", i, lits, lits2);
-            bad = true;
+            st.mismatches.push(format!("add step {}: added {:?}, removed {:?}", i, lits, lits2));
           }
           if need {
             if let Some(Proof::LRAT(steps)) = p {
               for s in steps {
-                let needed = &mut active.get_mut(&s.unsigned_abs()).expect("bad LRAT proof").0;
+                let needed = &mut active.get_mut(&s.unsigned_abs()).ok_or_else(|| bad_hint(s.unsigned_abs()))?.0;
                 if !*needed {
                   // unimplemented!();
                   *needed = true;
@@ -61,7 +157,7 @@ pub fn check_proof(mode: impl Mode, proof: File) -> io::Result<()> {
             }
           }
         } else {
-          dirty_add += 1;
+          st.dirty_add += 1;
           // eprintln!("added clause {} {:?} never finalized", i, lits);
         }
       },
@@ -71,143 +167,235 @@ pub fn check_proof(mode: impl Mode, proof: File) -> io::Result<()> {
         for (from, o) in removed {
           if let Some(s) = o {
             if active.insert(from, s).is_some() {
-              double_del += 1;
+              st.double_del += 1;
               // eprintln!("already deleted clause {} {:?}", i, active[&i]);
             }
           } else {
-            dirty_add += 1;
+            st.dirty_add += 1;
             // eprintln!("added clause {} {:?} never finalized", i, lits);
           }
         }
       },
       Step::Del(i, lits) => {
-        deleted += 1;
+        st.deleted += 1;
         if active.insert(i, (false, lits)).is_some() {
-          double_del += 1;
+          st.double_del += 1;
           // eprintln!("already deleted clause {} {:?}", i, active[&i]);
         }
       },
       Step::Final(i, lits) => {
-        fin += 1;
+        st.finalized += 1;
         if active.insert(i, (lits.is_empty(), lits)).is_some() {
-          double_fin += 1;
+          st.double_fin += 1;
           // eprintln!("already finalized clause {} {:?}", i, active[&i]);
         }
       },
       Step::Todo(i) => *todos.entry(i).or_insert(0i64) += 1,
-      Step::OrigXor(_i, _lits) => {
-        _orig_xor += 1;
+      Step::OrigXor(i, lits) => {
+        st.orig_xor += 1;
+        remove_xor(&mut st, &mut active_xor, "orig xor", i, &lits);
       },
-      Step::AddXor(_i, _lits, _p, u) => {
-        _add_xor += 1;
+      Step::AddXor(i, lits, p, u) => {
+        st.add_xor += 1;
+        remove_xor(&mut st, &mut active_xor, "add xor", i, &lits);
+        if let Some(Proof::LRAT(steps)) = p {
+          for s in steps {
+            if !active_xor.contains_key(&s.unsigned_abs()) { st.inactive_xor_hints += 1 }
+          }
+        }
         if let Some(Proof::Unit(units)) = u {
           for us in units {
-            let needed = &mut active.get_mut(&us).expect("bad unit hints for add-xor step").0;
+            let needed = &mut active.get_mut(&us).ok_or_else(|| bad_hint(us))?.0;
             if !*needed {
               *needed = true;
             }
           }
         }
       },
-      Step::DelXor(_i, _lits) => {
-        _del_xor += 1;
+      Step::DelXor(i, lits) => {
+        st.del_xor += 1;
+        if active_xor.insert(i, Xor::from_lits(&lits)).is_some() {
+          st.double_del += 1;
+        }
       },
-      Step::Imply(i, lits, _p) => {
-        _imply += 1;
+      Step::Imply(i, lits, p) => {
+        st.imply += 1;
         if let Some((_need, lits2)) = active.remove(&i) {
           if !subsumes(&lits2, &lits) {
-            eprintln!("imply step {}: added {:?}, removed {:?}", i, lits, lits2);
-            bad = true;
+            st.mismatches.push(format!("imply step {}: added {:?}, removed {:?}", i, lits, lits2));
           }
         } else {
-          dirty_imply += 1;
+          st.dirty_imply += 1;
           // eprintln!("implied clause {} {:?} never finalized", i, lits);
         }
+        if let Some(Proof::LRAT(steps)) = p {
+          for s in steps {
+            if !active_xor.contains_key(&s.unsigned_abs()) { st.inactive_xor_hints += 1 }
+          }
+        }
       },
-      Step::ImplyXor(_i, _lits, p) => {
-        _imply_xor += 1;
+      Step::ImplyXor(i, lits, p) => {
+        st.imply_xor += 1;
+        remove_xor(&mut st, &mut active_xor, "imply xor", i, &lits);
         if let Some(Proof::LRAT(steps)) = p {
           for s in steps {
-            let needed = &mut active.get_mut(&s.unsigned_abs()).expect("bad clause hints for imply-xor step").0;
+            let needed = &mut active.get_mut(&s.unsigned_abs()).ok_or_else(|| bad_hint(s.unsigned_abs()))?.0;
             if !*needed {
               *needed = true;
             }
           }
         }
       },
-      Step::FinalXor(_i, _lits) => {
-        _fin_xor += 1;
-      }, 
-      Step::OrigBnn(_i, _lits, _rhs, _out) => {
-        _orig_bnn += 1;
+      Step::FinalXor(i, lits) => {
+        st.fin_xor += 1;
+        if active_xor.insert(i, Xor::from_lits(&lits)).is_some() {
+          st.double_fin += 1;
+        }
+      },
+      Step::OrigBnn(i, lits, rhs, out) => {
+        st.orig_bnn += 1;
+        remove_bnn(&mut st, &mut active_bnn, "orig bnn", i, bnn(lits, rhs, out));
       },
-      Step::AddBnn(_i, _lits, _rhs, _out, p) => {
-        _add_bnn += 1;
+      Step::AddBnn(i, lits, rhs, out, p) => {
+        st.add_bnn += 1;
+        remove_bnn(&mut st, &mut active_bnn, "add bnn", i, bnn(lits, rhs, out));
         if let Some(Proof::LRAT(steps)) = p {
+          if let Some(b) = steps.first() {
+            if !active_bnn.contains_key(&b.unsigned_abs()) { st.inactive_bnn_hints += 1 }
+          }
           for s in steps.iter().skip(1) {
-            let needed = &mut active.get_mut(&s.unsigned_abs()).expect("bad clause hints for add-bnn step").0;
+            let needed = &mut active.get_mut(&s.unsigned_abs()).ok_or_else(|| bad_hint(s.unsigned_abs()))?.0;
             if !*needed {
               *needed = true;
             }
           }
         }
       },
-      Step::DelBnn(_i, _lits, _rhs, _out) => {
-        _del_bnn += 1;
+      Step::DelBnn(i, lits, rhs, out) => {
+        st.del_bnn += 1;
+        if active_bnn.insert(i, bnn(lits, rhs, out)).is_some() {
+          st.double_del += 1;
+        }
       },
-      Step::BnnImply(i, lits, _p, u) => {
-        _bnn_imply += 1;
+      Step::BnnImply(i, lits, p, u) => {
+        st.bnn_imply += 1;
         if let Some((_need, lits2)) = active.remove(&i) {
           if !subsumes(&lits2, &lits) {
-            eprintln!("bnn imply step {}: added {:?}, removed {:?}", i, lits, lits2);
-            bad = true;
+            st.mismatches.push(format!("bnn imply step {}: added {:?}, removed {:?}", i, lits, lits2));
           }
         } else {
-          dirty_bnn_imply += 1;
+          st.dirty_bnn_imply += 1;
           // eprintln!("implied clause {} {:?} never finalized", i, lits);
         }
-
+        if let Some(Proof::LRAT(steps)) = p {
+          if let Some(b) = steps.first() {
+            if !active_bnn.contains_key(&b.unsigned_abs()) { st.inactive_bnn_hints += 1 }
+          }
+        }
         if let Some(Proof::Unit(units)) = u {
           for us in units {
-            let needed = &mut active.get_mut(&us).expect("bad unit hints for bnn-imply step").0;
+            let needed = &mut active.get_mut(&us).ok_or_else(|| bad_hint(us))?.0;
             if !*needed {
               *needed = true;
             }
           }
         }
       },
-      Step::FinalBnn(_i, _lits, _rhs, _out) => {
-        _fin_bnn += 1;
-      }, 
+      Step::FinalBnn(i, lits, rhs, out) => {
+        st.fin_bnn += 1;
+        if active_bnn.insert(i, bnn(lits, rhs, out)).is_some() {
+          st.double_fin += 1;
+        }
+      },
     }
   }
-  println!("{} orig + {} added - {} deleted - {} finalized = {}",
-    orig, added, deleted, fin, orig + added - deleted - fin);
-  println!("{} missing proofs ({:.1}%)", missing, 100. * missing as f32 / added as f32);
   let mut todo_vec: Vec<_> = todos.into_iter().collect();
   todo_vec.sort_by_key(|(_, v)| -v);
-  for (k, v) in todo_vec.into_iter().take(5).filter(|&(_, v)| v != 0) {
+  st.todos = todo_vec.into_iter().take(5).filter(|&(_, v)| v != 0).collect();
+  st.unjustified = active.len();
+  st.unjustified_xor = active_xor.len();
+  st.unjustified_bnn = active_bnn.len();
+  Ok(st)
+}
+
+// The XOR `i` is introduced here, so it should have been deleted or finalized as the same XOR.
+fn remove_xor(st: &mut ProofStats, active: &mut HashMap<u64, Xor>, kind: &str, i: u64, lits: &[i64]) {
+  let x = Xor::from_lits(lits);
+  match active.remove(&i) {
+    None => st.dirty_xor += 1,
+    Some(x2) => if x != x2 {
+      st.mismatches.push(format!("{} step {}: added {:?}, removed {:?}", kind, i, x, x2));
+    }
+  }
+}
+
+fn remove_bnn(st: &mut ProofStats, active: &mut HashMap<u64, Bnn>, kind: &str, i: u64, b: Bnn) {
+  match active.remove(&i) {
+    None => st.dirty_bnn += 1,
+    Some(b2) => if b != b2 {
+      st.mismatches.push(format!("{} step {}: added {:?}, removed {:?}", kind, i, b, b2));
+    }
+  }
+}
+
+/// Print the statistics in the format of `frat-xor stat`, with problems on stderr.
+pub fn print_stats(st: &ProofStats) {
+  for m in &st.mismatches { eprintln!("{}", m) }
+  let implied = st.imply + st.bnn_imply;
+  if implied == 0 {
+    println!("{} orig + {} added - {} deleted - {} finalized = {}",
+      st.orig, st.added, st.deleted, st.finalized, st.orig + st.added - st.deleted - st.finalized);
+  } else {
+    println!("{} orig + {} added + {} implied ({} by XORs, {} by BNNs) - {} deleted - {} finalized = {}",
+      st.orig, st.added, implied, st.imply, st.bnn_imply, st.deleted, st.finalized,
+      st.orig + st.added + implied - st.deleted - st.finalized);
+  }
+  println!("{} missing proofs ({:.1}%)", st.missing, 100. * st.missing as f32 / st.added as f32);
+  if st.orig_xor + st.add_xor + st.imply_xor + st.del_xor + st.fin_xor != 0 {
+    println!("XORs: {} orig + {} added + {} implied - {} deleted - {} finalized = {}",
+      st.orig_xor, st.add_xor, st.imply_xor, st.del_xor, st.fin_xor,
+      st.orig_xor + st.add_xor + st.imply_xor - st.del_xor - st.fin_xor);
+  }
+  if st.orig_bnn + st.add_bnn + st.del_bnn + st.fin_bnn != 0 {
+    println!("BNNs: {} orig + {} added - {} deleted - {} finalized = {}",
+      st.orig_bnn, st.add_bnn, st.del_bnn, st.fin_bnn,
+      st.orig_bnn + st.add_bnn - st.del_bnn - st.fin_bnn);
+  }
+  for (k, v) in &st.todos {
     println!("type {}: {}", k, v);
   }
-  if dirty_orig != 0 || dirty_add != 0 || dirty_imply != 0 || dirty_bnn_imply != 0 {
-    eprintln!("{} original + {} added + {} implied + {} bnn-implied never finalized", dirty_orig, dirty_add, dirty_imply, dirty_bnn_imply);
-    bad = true;
+  if st.dirty_orig != 0 || st.dirty_add != 0 || st.dirty_imply != 0 || st.dirty_bnn_imply != 0 {
+    eprintln!("{} original + {} added + {} implied + {} bnn-implied never finalized",
+      st.dirty_orig, st.dirty_add, st.dirty_imply, st.dirty_bnn_imply);
   }
-  if double_del != 0 || double_fin != 0 {
-    eprintln!("{} double deletes + {} double finalized", double_del, double_fin);
-    bad = true;
+  if st.dirty_xor != 0 || st.dirty_bnn != 0 {
+    eprintln!("{} XORs + {} BNNs never finalized", st.dirty_xor, st.dirty_bnn);
   }
-  if !active.is_empty() {
-    eprintln!("{} unjustified", active.len());
-    bad = true;
+  if st.double_del != 0 || st.double_fin != 0 {
+    eprintln!("{} double deletes + {} double finalized", st.double_del, st.double_fin);
+  }
+  if st.unjustified != 0 {
+    eprintln!("{} unjustified", st.unjustified);
+  }
+  if st.unjustified_xor != 0 || st.unjustified_bnn != 0 {
+    eprintln!("{} unjustified XORs + {} unjustified BNNs", st.unjustified_xor, st.unjustified_bnn);
+  }
+  if st.inactive_xor_hints != 0 || st.inactive_bnn_hints != 0 {
+    eprintln!("{} hints to inactive XORs + {} hints to inactive BNNs",
+      st.inactive_xor_hints, st.inactive_bnn_hints);
   }
-  if bad { exit(1) }
-  Ok(())
 }
 
-pub fn main<I: Iterator<Item=String>>(mut args: I) -> io::Result<()> {
-  let mut proof = File::open(args.next().expect("missing proof file"))?;
+pub fn main<I: Iterator<Item=String>>(args: I) -> Result<()> {
+  let (json, files): (Vec<_>, Vec<_>) = args.partition(|s| s == "--json");
+  let path = files.into_iter().next()
+    .unwrap_or_else(|| usage_error("Missing proof file for `frat-xor stat`", "frat-xor stat FRATFILE [--json]"));
+  let mut proof = File::open(path)?;
+  let json = !json.is_empty();
   let bin = detect_binary(&mut proof)?;
-  if bin { check_proof(Bin, proof) }
-  else { check_proof(Ascii, proof) }
+  let st = if bin { check_proof(Bin, proof)? }
+  else { check_proof(Ascii, proof)? };
+  if json { println!("{}", st.to_json()) } else { print_stats(&st) }
+  if !st.is_ok() { exit(1) }
+  Ok(())
 }
@@ -2,9 +2,9 @@ use std::fs::File;
 use std::io::{self, BufReader, BufWriter, Read, Write};
 
 pub fn main(mut args: impl Iterator<Item=String>) -> io::Result<()> {
-  let mut r = BufReader::new(File::open(&args.next().expect("missing input file"))?)
+  let mut r = BufReader::new(File::open(args.next().expect("missing input file"))?)
     .bytes().map(Result::unwrap);
-  let w = &mut BufWriter::new(File::create(&args.next().expect("missing output file"))?);
+  let w = &mut BufWriter::new(File::create(args.next().expect("missing output file"))?);
 
   loop {
     match r.next() {
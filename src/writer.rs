@@ -0,0 +1,255 @@
+use std::io::Write;
+use crate::HashSet;
+use crate::error::Result;
+use crate::parser::{Ascii, Bin, StepRef, ProofRef};
+use crate::serialize::{Serialize, ModeWriter};
+
+/// The IDs of the clauses, XORs and BNNs that are currently live.
+#[derive(Default)]
+struct LiveIds {
+  clauses: HashSet<u64>,
+  xors: HashSet<u64>,
+  bnns: HashSet<u64>,
+}
+
+fn introduce(set: &mut HashSet<u64>, what: &str, i: u64) -> Result<()> {
+  ensure!(set.insert(i), malformed!("{} ID {} is already in use", what, i));
+  Ok(())
+}
+
+fn retire(set: &mut HashSet<u64>, what: &str, i: u64) -> Result<()> {
+  ensure!(set.remove(&i), malformed!("{} {} does not exist", what, i));
+  Ok(())
+}
+
+/// A writer for FRAT-XOR proofs, with one method per step kind.
+///
+/// The output is binary or ASCII depending on `bin`. If `debug` is set (the default in debug
+/// builds), the writer keeps track of the live IDs and rejects steps that reuse a live ID or
+/// delete or finalize a dead one, and [`finish`](Self::finish) rejects proofs that leave
+/// anything unfinalized. Hints are not checked.
+pub struct ProofWriter<W> {
+  w: W,
+  bin: bool,
+  live: Option<LiveIds>,
+}
+
+impl<W: Write> ProofWriter<W> {
+  pub fn new(w: W, bin: bool) -> Self {
+    ProofWriter { w, bin, live: if cfg!(debug_assertions) { Some(LiveIds::default()) } else { None } }
+  }
+
+  /// Turn ID tracking on or off. This should be done before the first step.
+  pub fn debug(mut self, debug: bool) -> Self {
+    self.live = if debug { Some(LiveIds::default()) } else { None };
+    self
+  }
+
+  /// The underlying writer.
+  pub fn get_mut(&mut self) -> &mut W { &mut self.w }
+
+  fn write(&mut self, s: StepRef<'_>) -> Result<()> {
+    if self.bin { Serialize::<Bin>::write(&s, &mut ModeWriter(Bin, &mut self.w))? }
+    else { Serialize::<Ascii>::write(&s, &mut ModeWriter(Ascii, &mut self.w))? }
+    Ok(())
+  }
+
+  fn track(&mut self, f: impl FnOnce(&mut LiveIds) -> Result<()>) -> Result<()> {
+    match &mut self.live {
+      Some(live) => f(live),
+      None => Ok(()),
+    }
+  }
+
+  pub fn comment(&mut self, s: &str) -> Result<()> { self.write(StepRef::Comment(s)) }
+
+  pub fn orig(&mut self, i: u64, lits: &[i64]) -> Result<()> {
+    self.track(|l| introduce(&mut l.clauses, "clause", i))?;
+    self.write(StepRef::Orig(i, lits))
+  }
+
+  /// Add a clause, with optional LRAT hints. Without hints, the clause may also need
+  /// propagation over XORs, which `elab` turns into clause-from-XOR steps.
+  pub fn add(&mut self, i: u64, lits: &[i64], hints: Option<&[i64]>) -> Result<()> {
+    self.track(|l| introduce(&mut l.clauses, "clause", i))?;
+    self.write(StepRef::add(i, lits, hints))
+  }
+
+  pub fn del(&mut self, i: u64, lits: &[i64]) -> Result<()> {
+    self.track(|l| retire(&mut l.clauses, "clause", i))?;
+    self.write(StepRef::Del(i, lits))
+  }
+
+  pub fn finalize(&mut self, i: u64, lits: &[i64]) -> Result<()> {
+    self.track(|l| retire(&mut l.clauses, "clause", i))?;
+    self.write(StepRef::Final(i, lits))
+  }
+
+  /// Move clauses from the first ID of each pair to the second.
+  pub fn reloc(&mut self, relocs: &[(u64, u64)]) -> Result<()> {
+    self.track(|l| {
+      for &(from, _) in relocs { retire(&mut l.clauses, "clause", from)? }
+      for &(_, to) in relocs { introduce(&mut l.clauses, "clause", to)? }
+      Ok(())
+    })?;
+    self.write(StepRef::Reloc(relocs))
+  }
+
+  pub fn todo(&mut self, i: u64) -> Result<()> { self.write(StepRef::Todo(i)) }
+
+  pub fn orig_xor(&mut self, i: u64, lits: &[i64]) -> Result<()> {
+    self.track(|l| introduce(&mut l.xors, "XOR", i))?;
+    self.write(StepRef::OrigXor(i, lits))
+  }
+
+  /// Add an XOR as the sum of the XORs `hints`, with optional unit clauses used to simplify it.
+  /// Without hints, `elab` finds the XORs to add up itself. It also works out the units needed.
+  pub fn add_xor(&mut self, i: u64, lits: &[i64], hints: Option<&[i64]>, units: Option<&[u64]>) -> Result<()> {
+    self.track(|l| introduce(&mut l.xors, "XOR", i))?;
+    self.write(StepRef::AddXor(i, lits, hints.map(ProofRef::LRAT), units.map(ProofRef::Unit)))
+  }
+
+  pub fn del_xor(&mut self, i: u64, lits: &[i64]) -> Result<()> {
+    self.track(|l| retire(&mut l.xors, "XOR", i))?;
+    self.write(StepRef::DelXor(i, lits))
+  }
+
+  pub fn finalize_xor(&mut self, i: u64, lits: &[i64]) -> Result<()> {
+    self.track(|l| retire(&mut l.xors, "XOR", i))?;
+    self.write(StepRef::FinalXor(i, lits))
+  }
+
+  /// Add a clause implied by the XORs `hints`, which `elab` finds itself if they are left out.
+  pub fn imply(&mut self, i: u64, lits: &[i64], hints: Option<&[i64]>) -> Result<()> {
+    self.track(|l| introduce(&mut l.clauses, "clause", i))?;
+    self.write(StepRef::imply(i, lits, hints))
+  }
+
+  /// Add an XOR implied by the clauses `hints`.
+  pub fn imply_xor(&mut self, i: u64, lits: &[i64], hints: Option<&[i64]>) -> Result<()> {
+    self.track(|l| introduce(&mut l.xors, "XOR", i))?;
+    self.write(StepRef::imply_xor(i, lits, hints))
+  }
+
+  pub fn orig_bnn(&mut self, i: u64, lits: &[i64], cutoff: i64, out: i64) -> Result<()> {
+    self.track(|l| introduce(&mut l.bnns, "BNN", i))?;
+    self.write(StepRef::OrigBnn(i, lits, cutoff, out))
+  }
+
+  pub fn add_bnn(&mut self, i: u64, lits: &[i64], cutoff: i64, out: i64, hints: Option<&[i64]>) -> Result<()> {
+    self.track(|l| introduce(&mut l.bnns, "BNN", i))?;
+    self.write(StepRef::add_bnn(i, lits, cutoff, out, hints))
+  }
+
+  pub fn del_bnn(&mut self, i: u64, lits: &[i64], cutoff: i64, out: i64) -> Result<()> {
+    self.track(|l| retire(&mut l.bnns, "BNN", i))?;
+    self.write(StepRef::DelBnn(i, lits, cutoff, out))
+  }
+
+  pub fn finalize_bnn(&mut self, i: u64, lits: &[i64], cutoff: i64, out: i64) -> Result<()> {
+    self.track(|l| retire(&mut l.bnns, "BNN", i))?;
+    self.write(StepRef::FinalBnn(i, lits, cutoff, out))
+  }
+
+  /// Add a clause implied by the BNN `hints[0]`, with optional unit clauses.
+  /// The units are only written if there are hints, and `elab` works out a minimal set itself.
+  pub fn bnn_imply(&mut self, i: u64, lits: &[i64], hints: Option<&[i64]>, units: Option<&[u64]>) -> Result<()> {
+    self.track(|l| introduce(&mut l.clauses, "clause", i))?;
+    self.write(StepRef::BnnImply(i, lits, hints.map(ProofRef::LRAT), units.map(ProofRef::Unit)))
+  }
+
+  /// Flush the proof and return the underlying writer. With ID tracking on, this fails
+  /// if a clause, XOR or BNN was never deleted or finalized.
+  pub fn finish(mut self) -> Result<W> {
+    if let Some(live) = &self.live {
+      let first = |set: &HashSet<u64>| set.iter().min().copied();
+      if let Some(i) = first(&live.clauses) { return Err(malformed!("clause {} was never finalized", i)) }
+      if let Some(i) = first(&live.xors) { return Err(malformed!("XOR {} was never finalized", i)) }
+      if let Some(i) = first(&live.bnns) { return Err(malformed!("BNN {} was never finalized", i)) }
+    }
+    self.w.flush()?;
+    Ok(self.w)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn proof(bin: bool) -> Vec<u8> {
+    let mut p = ProofWriter::new(vec![], bin).debug(true);
+    p.orig(1, &[1, -2]).unwrap();
+    p.orig_xor(1, &[-1, 2]).unwrap();
+    p.add(2, &[-2], Some(&[1])).unwrap();
+    p.del(1, &[1, -2]).unwrap();
+    p.finalize(2, &[-2]).unwrap();
+    p.finalize_xor(1, &[-1, 2]).unwrap();
+    p.finish().unwrap()
+  }
+
+  #[test] fn ascii() {
+    assert_eq!(String::from_utf8(proof(false)).unwrap(), "\
+      o 1  1 -2 0\n\
+      o x 1  -1 2 0\n\
+      a 2  -2 0  l 1 0\n\
+      d 1  1 -2 0\n\
+      f 2  -2 0\n\
+      f x 1  -1 2 0\n");
+  }
+
+  #[test] fn binary() {
+    // IDs are written as they are, literals and hints as 2|l| + (l < 0)
+    assert_eq!(proof(true), [
+      &b"o\x01\x02\x05\0"[..],
+      b"o\0x\x01\x03\x04\0",
+      b"a\x02\x05\0l\x02\0",
+      b"d\x01\x02\x05\0",
+      b"f\x02\x05\0",
+      b"f\0x\x01\x03\x04\0",
+    ].concat());
+  }
+
+  #[test] fn duplicate_id() {
+    let mut p = ProofWriter::new(vec![], false).debug(true);
+    p.orig(1, &[1]).unwrap();
+    assert_eq!(p.add(1, &[2], None).unwrap_err().cause, "clause ID 1 is already in use");
+    p.orig(2, &[2]).unwrap();
+    assert_eq!(p.reloc(&[(1, 2)]).unwrap_err().cause, "clause ID 2 is already in use");
+    p.orig_xor(1, &[1, 2]).unwrap();
+    assert_eq!(p.imply_xor(1, &[1, 2], None).unwrap_err().cause, "XOR ID 1 is already in use");
+    p.orig_bnn(1, &[1, 2], 1, 0).unwrap();
+    assert_eq!(p.add_bnn(1, &[1, 2], 1, 0, None).unwrap_err().cause, "BNN ID 1 is already in use");
+  }
+
+  #[test] fn delete_unknown() {
+    let mut p = ProofWriter::new(vec![], false).debug(true);
+    assert_eq!(p.del(1, &[1]).unwrap_err().cause, "clause 1 does not exist");
+    assert_eq!(p.reloc(&[(1, 2)]).unwrap_err().cause, "clause 1 does not exist");
+    assert_eq!(p.del_xor(1, &[1]).unwrap_err().cause, "XOR 1 does not exist");
+    assert_eq!(p.finalize_bnn(1, &[1], 1, 0).unwrap_err().cause, "BNN 1 does not exist");
+    p.orig(1, &[1]).unwrap();
+    p.finalize(1, &[1]).unwrap();
+    assert_eq!(p.finalize(1, &[1]).unwrap_err().cause, "clause 1 does not exist");
+  }
+
+  #[test] fn missing_final() {
+    let mut p = ProofWriter::new(vec![], false).debug(true);
+    p.orig(2, &[1]).unwrap();
+    p.orig(1, &[2]).unwrap();
+    assert_eq!(p.finish().unwrap_err().cause, "clause 1 was never finalized");
+    let mut p = ProofWriter::new(vec![], false).debug(true);
+    p.orig_xor(1, &[1]).unwrap();
+    assert_eq!(p.finish().unwrap_err().cause, "XOR 1 was never finalized");
+    let mut p = ProofWriter::new(vec![], false).debug(true);
+    p.orig_bnn(1, &[1], 1, 0).unwrap();
+    assert_eq!(p.finish().unwrap_err().cause, "BNN 1 was never finalized");
+  }
+
+  #[test] fn no_tracking() {
+    let mut p = ProofWriter::new(vec![], false).debug(false);
+    p.orig(1, &[1]).unwrap();
+    p.orig(1, &[1]).unwrap();
+    p.del(2, &[2]).unwrap();
+    p.finish().unwrap();
+  }
+}
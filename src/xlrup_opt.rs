@@ -0,0 +1,316 @@
+//! Post-processing of existing XLRUP proofs: drop the steps that the empty clause does not
+//! depend on, renumber the remaining clauses, XORs and BNNs densely, and delete each of them
+//! right after its last use.
+
+use std::cell::Cell;
+use std::fs::{File, read_to_string};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom};
+use crate::{HashMap, HashSet};
+use crate::error::{FratError, Result, usage_error};
+use crate::elab::{check_xlrup, split_xnf};
+use crate::parser::{detect_binary, Ascii, Bin, XLRUPParser, XLRUPStep, XLRUPStepRef};
+use crate::serialize::{ModeWrite, ModeWriter, Serialize};
+
+/// The ID namespaces of XLRUP.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Ns { Clause, Xor, Bnn }
+
+/// Call `f` on every ID in the step, with its namespace and whether the step introduces it
+/// (as opposed to using it as a hint). Uses are visited before the introduced ID.
+/// Deletions and comments have no IDs.
+fn visit(s: &mut XLRUPStep, mut f: impl FnMut(Ns, bool, &mut u64)) {
+  fn hints(ns: Ns, is: &mut [i64], f: &mut impl FnMut(Ns, bool, &mut u64)) {
+    for h in is {
+      let mut u = h.unsigned_abs();
+      f(ns, false, &mut u);
+      *h = if *h < 0 { -(u as i64) } else { u as i64 };
+    }
+  }
+  match s {
+    XLRUPStep::Comment(..) | XLRUPStep::Del(..) | XLRUPStep::DelXor(_) | XLRUPStep::DelBnn(_) => {}
+    XLRUPStep::Add(i, _, is) => { hints(Ns::Clause, is, &mut f); f(Ns::Clause, true, i) }
+    XLRUPStep::OrigXor(i, _) => f(Ns::Xor, true, i),
+    XLRUPStep::AddXor(i, _, is, u) => {
+      hints(Ns::Xor, is, &mut f);
+      for c in u { f(Ns::Clause, false, c) }
+      f(Ns::Xor, true, i)
+    }
+    XLRUPStep::Imply(i, _, is) => { hints(Ns::Xor, is, &mut f); f(Ns::Clause, true, i) }
+    XLRUPStep::ImplyXor(i, _, is) => { hints(Ns::Clause, is, &mut f); f(Ns::Xor, true, i) }
+    XLRUPStep::OrigBnn(i, _, _, _) => f(Ns::Bnn, true, i),
+    XLRUPStep::AddBnn(i, _, _, _, is) => {
+      if let Some((b, cs)) = is.split_first_mut() {
+        hints(Ns::Bnn, std::slice::from_mut(b), &mut f);
+        hints(Ns::Clause, cs, &mut f);
+      }
+      f(Ns::Bnn, true, i)
+    }
+    XLRUPStep::BnnImply(i, _, b, u) => {
+      f(Ns::Bnn, false, b);
+      for c in u { f(Ns::Clause, false, c) }
+      f(Ns::Clause, true, i)
+    }
+  }
+}
+
+/// The constraint introduced by a step, if any.
+fn introduces(s: &XLRUPStep) -> Option<(Ns, u64)> {
+  match *s {
+    XLRUPStep::Add(i, _, _) | XLRUPStep::Imply(i, _, _) | XLRUPStep::BnnImply(i, _, _, _) => Some((Ns::Clause, i)),
+    XLRUPStep::OrigXor(i, _) | XLRUPStep::AddXor(i, _, _, _) | XLRUPStep::ImplyXor(i, _, _) => Some((Ns::Xor, i)),
+    XLRUPStep::OrigBnn(i, _, _, _) | XLRUPStep::AddBnn(i, _, _, _, _) => Some((Ns::Bnn, i)),
+    _ => None,
+  }
+}
+
+fn is_empty_clause(s: &XLRUPStep) -> bool {
+  matches!(s, XLRUPStep::Add(_, ls, _) | XLRUPStep::Imply(_, ls, _) |
+    XLRUPStep::BnnImply(_, ls, _, _) if ls.is_empty())
+}
+
+/// Numbers of steps before and after optimization.
+#[derive(Clone, Debug, Default)]
+pub struct OptStats {
+  /// Derived clauses.
+  pub clauses: (usize, usize),
+  /// Original and derived XORs.
+  pub xors: (usize, usize),
+  /// Original and derived BNNs.
+  pub bnns: (usize, usize),
+  /// Original clauses that the proof does not use.
+  pub unused_origs: usize,
+}
+
+impl OptStats {
+  fn count(&mut self, s: &XLRUPStep, after: bool) {
+    let n = match introduces(s) {
+      Some((Ns::Clause, _)) => &mut self.clauses,
+      Some((Ns::Xor, _)) => &mut self.xors,
+      Some((Ns::Bnn, _)) => &mut self.bnns,
+      None => return,
+    };
+    if after { n.1 += 1 } else { n.0 += 1 }
+  }
+}
+
+/// Bytes of the proof read at a time by the backward pass.
+const CHUNK: usize = 1 << 16;
+
+/// The bytes of `file` from its start. A read error ends them, and is kept in `err`.
+fn read<'a>(mut file: &'a File, err: &'a Cell<Option<io::Error>>) -> io::Result<impl Iterator<Item=u8> + 'a> {
+  file.seek(SeekFrom::Start(0))?;
+  Ok(BufReader::new(file).bytes().map_while(move |b| b.map_err(|e| err.set(Some(e))).ok()))
+}
+
+/// A read error cuts the proof short, so it is reported instead of the parse error it causes.
+fn read_error(err: &Cell<Option<io::Error>>, e: FratError) -> FratError {
+  err.take().map_or(e, Into::into)
+}
+
+fn parse<'a>(bin: bool, it: impl Iterator<Item=u8> + 'a) -> Box<dyn Iterator<Item=Result<XLRUPStep>> + 'a> {
+  if bin { Box::new(XLRUPParser::from(Bin, it)) } else { Box::new(XLRUPParser::from(Ascii, it)) }
+}
+
+/// Reads the steps of a proof file from last to first, a chunk at a time as `BackParser` does
+/// for FRAT proofs. XLRUP cannot be split into steps from the back, so `bounds` holds the offsets
+/// of the steps, as found by a forward pass, followed by the end of the last step.
+struct BackSteps<'a> {
+  bin: bool,
+  file: &'a File,
+  bounds: &'a [usize],
+  buf: Vec<u8>,
+  /// The offset in the file of `buf[0]`.
+  buf_start: usize,
+}
+
+impl BackSteps<'_> {
+  fn step(&mut self) -> Result<Option<XLRUPStep>> {
+    let (&end, rest) = match self.bounds.split_last() { Some(p) => p, None => return Ok(None) };
+    let start = match rest.last() { Some(&start) => start, None => return Ok(None) };
+    self.bounds = rest;
+    if start < self.buf_start {
+      let from = start.min(end.saturating_sub(CHUNK));
+      self.buf.resize(end - from, 0);
+      let mut file = self.file;
+      file.seek(SeekFrom::Start(from as u64))?;
+      file.read_exact(&mut self.buf)?;
+      self.buf_start = from;
+    }
+    let bytes = &self.buf[start - self.buf_start..end - self.buf_start];
+    parse(self.bin, bytes.iter().copied()).next().transpose()
+  }
+}
+
+/// Optimize the XLRUP proof `proof` (binary if `bin`) of a formula with `n_cnf` clauses,
+/// writing the result to `w`. Steps after the first empty clause and all comments are dropped.
+/// The proof is read forwards, then backwards and then forwards again, writing as it goes,
+/// so only the step offsets and the live IDs are kept in memory.
+pub fn optimize<M>(
+  bin: bool, n_cnf: u64, proof: &File, w: &mut impl ModeWrite<M>
+) -> Result<OptStats> where for<'a> XLRUPStepRef<'a>: Serialize<M> {
+  let mut stats = OptStats::default();
+  let err = Cell::new(None);
+
+  // Find where the steps up to the first empty clause start.
+  let pos = Cell::new(0);
+  let mut bounds = vec![0];
+  let mut done = false;
+  for s in parse(bin, read(proof, &err)?.inspect(|_| pos.set(pos.get() + 1))) {
+    let s = s.map_err(|e| read_error(&err, e))?;
+    stats.count(&s, false);
+    bounds.push(pos.get());
+    if is_empty_clause(&s) { done = true; break }
+  }
+  if let Some(e) = err.take() { return Err(e.into()) }
+  ensure!(done, invalid!("did not find empty clause"));
+
+  // Backward pass: keep the steps introducing constraints that a kept step uses.
+  // A constraint is needed from its last use back to the nearest step introducing its ID,
+  // and is deleted right after that last use.
+  let n = bounds.len() - 1;
+  let mut need: HashSet<(Ns, u64)> = HashSet::default();
+  let mut keep = vec![false; n];
+  let mut dels: Vec<(usize, Ns, u64)> = vec![];
+  let mut back = BackSteps { bin, file: proof, bounds: &bounds, buf: vec![], buf_start: usize::MAX };
+  for j in (0..n).rev() {
+    let mut s = back.step()?.ok_or_else(|| malformed!("proof changed while reading it"))?;
+    if j == n - 1 || introduces(&s).is_some_and(|c| need.remove(&c)) {
+      keep[j] = true;
+      visit(&mut s, |ns, intro, &mut i| if !intro && need.insert((ns, i)) && j != n - 1 {
+        dels.push((j, ns, i))
+      });
+    }
+  }
+  for &(ns, i) in &need {
+    ensure!(ns == Ns::Clause && (1..=n_cnf).contains(&i),
+      malformed!("{:?} {} is used but never introduced", ns, i));
+  }
+  let unused: Vec<u64> = (1..=n_cnf).filter(|i| !need.contains(&(Ns::Clause, *i))).collect();
+  stats.unused_origs = unused.len();
+
+  // Forward pass: renumber densely, writing each kept step followed by its deletions.
+  // Original clauses keep their IDs.
+  let mut map: HashMap<(Ns, u64), u64> = HashMap::default();
+  let mut next = [n_cnf, 0, 0];
+  let mut k = n_cnf;
+  let mut unused = Some(unused);
+  for (j, s) in parse(bin, read(proof, &err)?).take(n).enumerate() {
+    if !keep[j] { continue }
+    let mut s = s.map_err(|e| read_error(&err, e))?;
+    let (kind, id) = s.kind();
+    let mut dead = None;
+    visit(&mut s, |ns, intro, i| if intro {
+      next[ns as usize] += 1;
+      map.insert((ns, *i), next[ns as usize]);
+      *i = next[ns as usize];
+    } else if ns != Ns::Clause || *i > n_cnf {
+      match map.get(&(ns, *i)) { Some(&k) => *i = k, None => dead = Some((ns, *i)) }
+    });
+    if let Some((ns, i)) = dead {
+      return Err(malformed!("{:?} {} is not live at this step", ns, i).at(kind, id))
+    }
+    stats.count(&s, true);
+    if !matches!(s, XLRUPStep::OrigXor(..) | XLRUPStep::OrigBnn(..)) {
+      if let Some(unused) = unused.take().filter(|u| !u.is_empty()) {
+        XLRUPStepRef::Del(k, &unused).write(w)?
+      }
+    }
+    s.write(w)?;
+    if let Some((Ns::Clause, i)) = introduces(&s) { k = i }
+    let [mut cs, mut xs, mut bs]: [Vec<u64>; 3] = Default::default();
+    while let Some(&(_, ns, i)) = dels.last().filter(|d| d.0 == j) {
+      dels.pop();
+      let i = if ns == Ns::Clause && i <= n_cnf { i } else {
+        map.remove(&(ns, i)).ok_or_else(|| malformed!("{:?} {} is not live at this step", ns, i).at(kind, id))?
+      };
+      [&mut cs, &mut xs, &mut bs][ns as usize].push(i)
+    }
+    cs.sort_unstable(); xs.sort_unstable(); bs.sort_unstable();
+    if !cs.is_empty() { XLRUPStepRef::Del(k, &cs).write(w)? }
+    if !xs.is_empty() { XLRUPStepRef::DelXor(&xs).write(w)? }
+    if !bs.is_empty() { XLRUPStepRef::DelBnn(&bs).write(w)? }
+  }
+  w.flush()?;
+  Ok(stats)
+}
+
+pub fn main(args: impl Iterator<Item=String>) -> Result<()> {
+  const USAGE: &str = "\
+    frat-xor xlrup-opt DIMACSFILE XLRUPFILE OUTFILE [-b] [-v]\n\n\
+    Note: options must appear in the specified order";
+  let mut args = args.peekable();
+  let mut next = |what| args.next()
+    .unwrap_or_else(|| usage_error(&format!("Missing {} for `frat-xor xlrup-opt`", what), USAGE));
+  let (dimacs, proof_path, out_path) = (next("input file"), next("proof file"), next("output file"));
+  let binary = matches!(args.peek(), Some(s) if s == "-b") && { args.next(); true };
+  let verify = matches!(args.peek(), Some(s) if s == "-v") && { args.next(); true };
+  if args.peek().is_some() { usage_error("Too many arguments to `frat-xor xlrup-opt`", USAGE) }
+
+  let (cnf, xnf) = split_xnf(&read_to_string(dimacs)?)?;
+  let n_cnf = cnf.len() as u64;
+  let mut proof = File::open(proof_path)?;
+  let bin = detect_binary(&mut proof)?;
+  let mut w = BufWriter::new(File::create(&out_path)?);
+  let stats = if binary { optimize(bin, n_cnf, &proof, &mut ModeWriter(Bin, &mut w))? }
+  else { optimize(bin, n_cnf, &proof, &mut ModeWriter(Ascii, &mut w))? };
+  drop(w);
+  println!("clauses: {} -> {}", stats.clauses.0, stats.clauses.1);
+  println!("XORs: {} -> {}", stats.xors.0, stats.xors.1);
+  println!("BNNs: {} -> {}", stats.bnns.0, stats.bnns.1);
+  println!("unused original clauses: {}", stats.unused_origs);
+
+  if verify {
+    let (out, err) = (File::open(&out_path)?, Cell::new(None));
+    let res = if binary { check_xlrup(Bin, cnf, &xnf, read(&out, &err)?) }
+    else { check_xlrup(Ascii, cnf, &xnf, read(&out, &err)?) };
+    res.map_err(|e| read_error(&err, e))?;
+    println!("VERIFIED");
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const XNF: &str = "p cnf 3 6\n1 0\n3 0\nx -1 2 0\nx 2 3 0\nb 1 2 3 0 3 0\nb -2 0 1 0\n";
+
+  // XORs 7 and 11, BNN 4 and clause 2 are never used
+  const PROOF: &str = "\
+    o x 5 -1 2 0\n\
+    o x 7 2 3 0\n\
+    o b 4 1 2 3 0 3 0\n\
+    o b 8 -2 0 1 0\n\
+    x 9 -1 2 0 5 0\n\
+    x 11 2 3 0 7 0\n\
+    i cx 10 -1 2 0 9 0\n\
+    i cb 20 -2 0 8 0\n\
+    30 0 1 10 20 0\n";
+
+  // Each XOR and BNN is deleted right after its last use, and the unused clause
+  // before the first derived step
+  const OPTIMIZED: &str = "\
+    o x 1 -1 2 0\n\
+    o b 1 -2 0 1 0\n\
+    2 d 2 0\n\
+    x 2 -1 2 0 1 0\n\
+    x d 1 0\n\
+    i cx 3 -1 2 0 2 0\n\
+    x d 2 0\n\
+    i cb 4 -2 0 1 0\n\
+    b d 1 0\n\
+    5 0 1 3 4 0\n";
+
+  #[test] fn drop_unused() {
+    let path = std::env::temp_dir().join(format!("frat-xor-opt-{}.xlrup", std::process::id()));
+    std::fs::write(&path, PROOF).unwrap();
+    let mut w = ModeWriter(Ascii, vec![]);
+    let stats = optimize(false, 2, &File::open(&path).unwrap(), &mut w);
+    std::fs::remove_file(&path).unwrap();
+    let stats = stats.unwrap();
+    assert_eq!(String::from_utf8(w.1.clone()).unwrap(), OPTIMIZED);
+    assert_eq!((stats.clauses, stats.xors, stats.bnns, stats.unused_origs), ((3, 3), (4, 2), (2, 1), 1));
+    let (cnf, xnf) = split_xnf(XNF).unwrap();
+    check_xlrup(Ascii, cnf, &xnf, w.1.into_iter()).unwrap();
+  }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+/// An XOR constraint `v_1 + ... + v_n = rhs` over GF(2), kept as a sorted list
+/// of distinct variables so that equal constraints compare equal.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Xor {
+  pub vars: Vec<i64>,
+  pub rhs: bool,
+}
+
+impl Xor {
+  /// Build an XOR from a list of literals, read as in the proof formats:
+  /// the literals sum to 1, and a negative literal `-v` stands for `v + 1`.
+  pub fn from_lits(lits: &[i64]) -> Self {
+    let mut vars: Vec<i64> = lits.iter().map(|l| l.abs()).collect();
+    vars.sort_unstable();
+    let mut x = Xor { vars: Vec::with_capacity(vars.len()), rhs: true };
+    for v in vars {
+      // repeated variables cancel in pairs
+      if x.vars.last() == Some(&v) { x.vars.pop(); } else { x.vars.push(v) }
+    }
+    x.rhs ^= lits.iter().filter(|&&l| l < 0).count() % 2 != 0;
+    x
+  }
+
+  /// Add another XOR to this one.
+  pub fn add(&mut self, other: &Xor) {
+    let mut out = Vec::with_capacity(self.vars.len() + other.vars.len());
+    let (mut i, mut j) = (0, 0);
+    while i < self.vars.len() && j < other.vars.len() {
+      let (a, b) = (self.vars[i], other.vars[j]);
+      if a < b { out.push(a); i += 1 }
+      else if b < a { out.push(b); j += 1 }
+      else { i += 1; j += 1 }
+    }
+    out.extend_from_slice(&self.vars[i..]);
+    out.extend_from_slice(&other.vars[j..]);
+    self.vars = out;
+    self.rhs ^= other.rhs;
+  }
+
+  /// Substitute the value making `lit` true into the constraint.
+  pub fn assign(&mut self, lit: i64) {
+    if let Ok(i) = self.vars.binary_search(&lit.abs()) {
+      self.vars.remove(i);
+      self.rhs ^= lit > 0;
+    }
+  }
+
+  /// The variables on which two XORs differ.
+  pub fn diff(&self, other: &Xor) -> Vec<i64> {
+    let mut d = self.clone();
+    d.add(other);
+    d.vars
+  }
+}
+
+impl fmt::Debug for Xor {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?} = {}", self.vars, self.rhs as u8)
+  }
+}